@@ -2,7 +2,9 @@
 
 in vec3 v_position;// <- world space
 in vec3 v_normal;// <- world space
+in vec3 v_tangent;// <- world space, along increasing u
 in vec2 v_uv;
+in vec2 v_curvature;// <- (gaussian, mean)
 
 uniform vec3 camera_pos;
 
@@ -20,6 +22,12 @@ uniform sampler2D rough_metal;
 #else
 uniform vec2 rough_metal;
 #endif
+#if defined(FALSE_COLOR_GAUSSIAN) || defined(FALSE_COLOR_MEAN)
+uniform float curvature_range = 1.0;
+#endif
+#if defined(ZEBRA) || defined(ISOPHOTES)
+uniform float stripe_count = 16.0;
+#endif
 
 out vec4 out_color;
 
@@ -111,8 +119,54 @@ mat3 cotangent_frame(vec3 normal, vec3 pos, vec2 uv) {
     return mat3(T * invmax, B * invmax, normal);
 }
 
+// Frame from the interpolated vertex tangent, falling back to screen-space derivatives for meshes
+// without tangents
+mat3 tangent_frame(vec3 normal, vec3 tangent, vec3 pos, vec2 uv) {
+    vec3 N = normalize(normal);
+    vec3 T = tangent - dot(tangent, N) * N;
+    if (dot(T, T) < 1e-12) {
+        return cotangent_frame(N, pos, uv);
+    }
+    T = normalize(T);
+    return mat3(T, cross(N, T), N);
+}
+
+// Diverging blue - white - red map of `value` in [-1, 1]
+vec3 false_color(float value) {
+    float t = clamp(value, -1.0, 1.0);
+    vec3 cold = vec3(0.1, 0.3, 1.0);
+    vec3 hot = vec3(1.0, 0.15, 0.1);
+    return t < 0.0 ? mix(vec3(1.0), cold, -t) : mix(vec3(1.0), hot, t);
+}
+
+// Antialiased square wave of period 1, white around integer values
+float stripes(float x) {
+    float width = fwidth(x);// <- wave below changes twice as fast, blurs over about two pixels
+    float wave = abs(fract(x) - 0.5) * 2.0;// <- triangle wave, 1 at integers
+    return smoothstep(0.5 - width, 0.5 + width, wave);
+}
+
 void main() {
-    #ifdef HAS_COLOR_TEXTURE
+    #if defined(ZEBRA) || defined(ISOPHOTES)
+    vec3 N = normalize(v_normal);
+    vec3 V = normalize(v_position - camera_pos);
+    #if defined(ZEBRA)
+    // Environment of horizontal light tubes, evenly spaced in elevation
+    vec3 R = reflect(V, N);
+    float shade = stripes(asin(clamp(R.y, -1.0, 1.0)) / M_PI * stripe_count);
+    #else
+    float incidence = max(0.0, dot(N, -V));
+    float shade = mix(0.2, 1.0, incidence) * stripes(incidence * stripe_count + 0.25);
+    #endif
+    out_color = vec4(vec3(shade), 1.0);
+    return;
+    #endif
+
+    #if defined(FALSE_COLOR_GAUSSIAN)
+    vec3 albedo = false_color(v_curvature.x / curvature_range);
+    #elif defined(FALSE_COLOR_MEAN)
+    vec3 albedo = false_color(v_curvature.y / curvature_range);
+    #elif defined(HAS_COLOR_TEXTURE)
     vec3 albedo = texture(color, v_uv).rgb;
     #else
     vec3 albedo = color;
@@ -136,7 +190,7 @@ void main() {
     }
 
         #ifdef HAS_NORMAL_TEXTURE
-    mat3 tbn = cotangent_frame(v_normal, v_position, v_uv);
+    mat3 tbn = tangent_frame(v_normal, v_tangent, v_position, v_uv);
     vec3 tangent_map = -(texture(normal_map, v_uv).xyz * 2. - 1.) * vec3(normal_amount, normal_amount, 1.);
     vec3 normal = normalize(tbn * tangent_map);// <- world space
     #else
@@ -3,6 +3,8 @@
 in vec3 position;
 in vec3 normal;
 in vec2 uv;
+in vec3 tangent;
+in vec2 curvature;
 
 uniform mat4 model;
 uniform mat4 view_proj;
@@ -10,6 +12,8 @@ uniform mat4 view_proj;
 out vec3 v_position;
 out vec2 v_uv;
 out vec3 v_normal;
+out vec3 v_tangent;
+out vec2 v_curvature;
 
 void main() {
     mat4 transform = view_proj * model;
@@ -19,4 +23,6 @@ void main() {
     vec4 pnormal = model * vec4(normal, 0.0);
     gl_Position = view_proj * gl_Position;
     v_normal = pnormal.xyz;
+    v_tangent = (model * vec4(tangent, 0.0)).xyz;
+    v_curvature = curvature;
 }
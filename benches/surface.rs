@@ -0,0 +1,60 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use glam::Vec3;
+use rand::prelude::*;
+
+use iafa_ig_projet::bezier::basis::BasisTable;
+use iafa_ig_projet::bezier::patch::BezierPatch;
+use iafa_ig_projet::bezier::surface::BezierSurface;
+use iafa_ig_projet::bezier::tessellation::TessellationBuilder;
+
+const RESOLUTION: usize = 64;
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let params = (0..RESOLUTION)
+        .map(|i| i as f32 / (RESOLUTION - 1) as f32)
+        .collect::<Vec<_>>();
+    let mut rng = StdRng::seed_from_u64(42);
+    let surfaces = [2usize, 4, 6, 11].map(|n| {
+        let points = (0..n * n).map(|_| rng.gen::<Vec3>());
+        BezierSurface::from(BezierPatch::new(n, n, points).unwrap())
+    });
+    let mut group = c.benchmark_group("Bezier surface");
+    for surface in &surfaces {
+        let degree = surface.patch().degree_u();
+        group.bench_with_input(
+            BenchmarkId::new("Pointwise derivatives", degree),
+            surface,
+            |b, surface| {
+                b.iter(|| {
+                    params.iter().for_each(|&v| {
+                        params.iter().for_each(|&u| {
+                            surface.derivatives(u, v);
+                        })
+                    })
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("Tabulated derivatives", degree),
+            surface,
+            |b, surface| {
+                b.iter(|| {
+                    let table = BasisTable::new(degree, &params);
+                    surface.patch().grid_derivatives(&table, &table).unwrap()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("Tessellation", degree),
+            surface,
+            |b, surface| {
+                let builder = TessellationBuilder::new(RESOLUTION, RESOLUTION);
+                b.iter(|| builder.build(surface).unwrap())
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,150 @@
+/// Abscissas and weights of the 5-point Gauss–Legendre quadrature over `[-1, 1]`.
+pub(crate) const GAUSS_LEGENDRE: [(f32, f32); 5] = [
+    (0., 0.568_888_9),
+    (-0.538_469_3, 0.478_628_67),
+    (0.538_469_3, 0.478_628_67),
+    (-0.906_179_8, 0.236_926_88),
+    (0.906_179_8, 0.236_926_88),
+];
+
+/// Binomial coefficient `n choose k`.
+pub fn binomial(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.;
+    }
+    let k = k.min(n - k);
+    (0..k).fold(1., |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Bernstein polynomial `B(i, n)` evaluated at `t`.
+pub fn bernstein(n: usize, i: usize, t: f64) -> f64 {
+    if i > n {
+        return 0.;
+    }
+    binomial(n, i) * t.powi(i as i32) * (1. - t).powi((n - i) as i32)
+}
+
+/// Bernstein basis of one degree, with its first and second derivatives, tabulated at a fixed
+/// set of parameters.
+///
+/// Evaluating a patch over a grid of parameters then reduces to small weighted sums of control
+/// points, see [`BezierPatch::grid_derivatives`](crate::bezier::patch::BezierPatch::grid_derivatives).
+#[derive(Debug, Clone)]
+pub struct BasisTable {
+    degree: usize,
+    params: Vec<f32>,
+    /// Per parameter, the `degree + 1` values of the basis, then of its first and second
+    /// derivatives.
+    values: Vec<f32>,
+}
+
+impl BasisTable {
+    pub fn new(degree: usize, params: &[f32]) -> Self {
+        let n = degree + 1;
+        let mut values = Vec::with_capacity(3 * n * params.len());
+        // Bases of degree `degree`, `degree - 1` and `degree - 2`, built up by the stable
+        // recurrence `B(i, d) = (1 - t) B(i, d - 1) + t B(i - 1, d - 1)`
+        let mut levels = [vec![0f32; n], vec![0f32; n], vec![0f32; n]];
+        for &t in params {
+            let mut basis = vec![1f32];
+            for d in 1..=degree {
+                if d + 2 > degree {
+                    levels[degree - d + 1][..d].copy_from_slice(&basis);
+                }
+                let mut next = vec![0f32; d + 1];
+                for (i, &b) in basis.iter().enumerate() {
+                    next[i] += (1. - t) * b;
+                    next[i + 1] += t * b;
+                }
+                basis = next;
+            }
+            levels[0].copy_from_slice(&basis);
+
+            values.extend_from_slice(&levels[0]);
+            let d = degree as f32;
+            values.extend((0..n).map(|i| {
+                if degree < 1 {
+                    return 0.;
+                }
+                let lower = |k: usize| if k < degree { levels[1][k] } else { 0. };
+                let previous = if i > 0 { lower(i - 1) } else { 0. };
+                d * (previous - lower(i))
+            }));
+            values.extend((0..n).map(|i| {
+                if degree < 2 {
+                    return 0.;
+                }
+                let lower = |k: Option<usize>| match k {
+                    Some(k) if k + 1 < degree => levels[2][k],
+                    _ => 0.,
+                };
+                d * (d - 1.)
+                    * (lower(i.checked_sub(2)) - 2. * lower(i.checked_sub(1)) + lower(Some(i)))
+            }));
+        }
+        Self {
+            degree,
+            params: params.to_vec(),
+            values,
+        }
+    }
+
+    /// Table at `count` parameters evenly spread over `[0, 1]`, ends included.
+    pub fn uniform(degree: usize, count: usize) -> Self {
+        let step = 1. / count.saturating_sub(1).max(1) as f32;
+        let params = (0..count).map(|k| k as f32 * step).collect::<Vec<_>>();
+        Self::new(degree, &params)
+    }
+
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    pub fn params(&self) -> &[f32] {
+        &self.params
+    }
+
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Basis values, first and second derivatives at the `k`-th parameter.
+    pub fn get(&self, k: usize) -> [&[f32]; 3] {
+        let n = self.degree + 1;
+        let start = 3 * n * k;
+        [0, 1, 2].map(|order| &self.values[start + order * n..start + (order + 1) * n])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::{bernstein, BasisTable};
+
+    #[test]
+    fn basis_table() {
+        for degree in [0, 1, 2, 3, 7] {
+            let table = BasisTable::uniform(degree, 9);
+            for (k, &t) in table.params().iter().enumerate() {
+                let [values, first, second] = table.get(k);
+                let h = 1e-4;
+                for i in 0..=degree {
+                    let b = |t: f64| bernstein(degree, i, t);
+                    let t = t as f64;
+                    assert!((values[i] as f64 - b(t)).abs() < 1e-6);
+                    // Bernstein polynomials extend smoothly beyond the domain
+                    let d1 = (b(t + h) - b(t - h)) / (2. * h);
+                    let d2 = (b(t + h) - 2. * b(t) + b(t - h)) / (h * h);
+                    assert!((first[i] as f64 - d1).abs() < 1e-4 * (1. + d1.abs()));
+                    assert!((second[i] as f64 - d2).abs() < 1e-3 * (1. + d2.abs()));
+                }
+                assert!((values.iter().sum::<f32>() - 1.).abs() < 1e-6);
+            }
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use anyhow::Context;
+use glam::{Mat3, Vec3};
+
+use crate::bezier::curve::BezierCurve;
+use crate::bezier::linalg;
+use crate::bezier::nurbs::NurbsSurface;
+use crate::bezier::patch::BezierPatch;
+use crate::bezier::surface::BezierSurface;
+
+/// Degree along `v` of lofted surfaces through enough sections.
+const LOFT_DEGREE: usize = 3;
+
+/// Surface constructors from curves. Input curves run along `u` in the resulting surfaces.
+impl BezierSurface {
+    /// Linear extrusion of `profile` by `direction`.
+    pub fn extrusion(profile: &BezierCurve<Vec3>, direction: Vec3) -> anyhow::Result<Self> {
+        let profile = profile.control_points();
+        BezierPatch::from_rows([
+            profile.clone(),
+            profile.iter().map(|&p| p + direction).collect(),
+        ])
+        .map(Self::from)
+    }
+
+    /// Ruled surface joining `a` (at `v = 0`) and `b` (at `v = 1`) with straight lines.
+    pub fn ruled(a: &BezierCurve<Vec3>, b: &BezierCurve<Vec3>) -> anyhow::Result<Self> {
+        BezierPatch::from_rows(match_degrees([a, b]).iter().map(|c| c.control_points()))
+            .map(Self::from)
+    }
+}
+
+/// Surface constructors from curves that need several polynomial pieces or rational weights.
+/// Input curves run along `u` in the resulting surfaces.
+impl NurbsSurface {
+    /// Surface of revolution of `profile` around the axis going through `origin` along `axis`,
+    /// rotating counter-clockwise by `angle` radians along `v`, up to a full turn.
+    ///
+    /// The rotation is exact: it is made of rational quadratic circular arcs of at most a quarter
+    /// turn each.
+    pub fn revolution(
+        profile: &BezierCurve<Vec3>,
+        origin: Vec3,
+        axis: Vec3,
+        angle: f32,
+    ) -> anyhow::Result<Self> {
+        let axis = axis.try_normalize().context("Axis of revolution is null")?;
+        anyhow::ensure!(
+            angle != 0. && angle.abs() <= TAU + 1e-6,
+            "Angle of revolution {} is not within a full turn",
+            angle
+        );
+        let profile = profile.control_points();
+        let degree = profile.len().saturating_sub(1);
+        let spans = (angle.abs() / FRAC_PI_2 - 1e-4).ceil().max(1.) as usize;
+        let step = angle / spans as f32;
+        let middle = (step / 2.).cos();
+
+        // Control points of each arc: its ends on the circle, and the intersection of their
+        // tangents in between, weighted by the cosine of half the arc
+        let arc = (0..=2 * spans)
+            .map(|k| {
+                let (sin, cos) = (k as f32 * step / 2.).sin_cos();
+                if k % 2 == 0 {
+                    (cos, sin, 1.)
+                } else {
+                    (cos / middle, sin / middle, middle)
+                }
+            })
+            .collect::<Vec<_>>();
+        let points = arc.iter().flat_map(|&(cos, sin, _)| {
+            profile.iter().map(move |&point| {
+                let center = origin + axis * axis.dot(point - origin);
+                let radial = point - center;
+                center + cos * radial + sin * axis.cross(radial)
+            })
+        });
+        let weights = arc
+            .iter()
+            .flat_map(|&(_, _, weight)| profile.iter().map(move |_| weight));
+        let knots_u = bezier_knots(degree);
+        let mut knots_v = vec![0.; 3];
+        for k in 1..spans {
+            knots_v.extend([k as f32 / spans as f32; 2]);
+        }
+        knots_v.extend([1.; 3]);
+        Self::new(degree, 2, knots_u, knots_v, profile.len(), points)?.with_weights(weights)
+    }
+
+    /// Surface interpolating the given sections in order, which are degree-elevated to a common
+    /// degree. Sections are placed along `v` proportionally to the distance between them.
+    ///
+    /// Along `v`, the surface is a cubic B-spline, or a single Bézier span through fewer than 4
+    /// sections.
+    pub fn loft(sections: &[BezierCurve<Vec3>]) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            sections.len() >= 2,
+            "Lofting needs at least 2 sections, got {}",
+            sections.len()
+        );
+        let sections = match_degrees(sections.iter())
+            .into_iter()
+            .map(|curve| curve.control_points())
+            .collect::<Vec<_>>();
+
+        // Chord-length parameterization, averaged over the control points
+        let mut params = vec![0.];
+        for pair in sections.windows(2) {
+            let distance = pair[0]
+                .iter()
+                .zip(&pair[1])
+                .map(|(a, b)| a.distance(*b))
+                .sum::<f32>();
+            params.push(params.last().unwrap() + distance);
+        }
+        let total = *params.last().unwrap();
+        anyhow::ensure!(total > 0., "Sections are all identical");
+        let params = params.into_iter().map(|p| p / total).collect::<Vec<_>>();
+
+        // Knots averaging the parameters, so that every span holds sections (Piegl & Tiller,
+        // The NURBS Book, eq. 9.8)
+        let (height, degree) = (sections.len(), (sections.len() - 1).min(LOFT_DEGREE));
+        let mut knots = vec![0.; degree + 1];
+        for j in 1..height - degree {
+            knots.push(params[j..j + degree].iter().sum::<f32>() / degree as f32);
+        }
+        knots.extend(vec![1.; degree + 1]);
+        let matrix = params
+            .iter()
+            .flat_map(|&t| bspline_basis(&knots, degree, t))
+            .collect::<Vec<_>>();
+
+        let width = sections[0].len();
+        let mut points = vec![Vec3::ZERO; width * height];
+        for i in 0..width {
+            let mut column = sections
+                .iter()
+                .map(|section| section[i].as_dvec3())
+                .collect::<Vec<_>>();
+            linalg::solve(&mut matrix.clone(), &mut column).context("Cannot loft sections")?;
+            for (j, point) in column.into_iter().enumerate() {
+                points[j * width + i] = point.as_vec3();
+            }
+        }
+        let knots_u = bezier_knots(width - 1);
+        Self::new(width - 1, degree, knots_u, knots, width, points)
+    }
+
+    /// Sweeps `profile` along `path`, keeping its orientation relative to the path with
+    /// rotation-minimizing frames. The profile is given in place at the start of the path.
+    ///
+    /// The sweep is approximated by lofting through `sections` copies of the profile, see
+    /// [`Self::loft`]; more sections give a closer fit to long or winding paths.
+    pub fn sweep(
+        profile: &BezierCurve<Vec3>,
+        path: &BezierCurve<Vec3>,
+        sections: usize,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            sections >= 2,
+            "Sweeping needs at least 2 sections, got {}",
+            sections
+        );
+        let tangent = path.derivative();
+        let stations = (0..sections)
+            .map(|k| k as f32 / (sections - 1) as f32)
+            .map(|t| {
+                let direction = tangent.get_point(t);
+                let direction = direction
+                    .try_normalize()
+                    .or_else(|| (path.get_point(1.) - path.get_point(0.)).try_normalize())
+                    .context("Sweep path is degenerate")?;
+                Ok((path.get_point(t), direction))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        // Rotation-minimizing frames by double reflection (Wang et al., 2008)
+        let (start, start_tangent) = stations[0];
+        let mut reference = start_tangent.any_orthonormal_pair().0;
+        let frame = |tangent: Vec3, reference: Vec3| {
+            Mat3::from_cols(reference, tangent.cross(reference), tangent)
+        };
+        let start_frame = frame(start_tangent, reference);
+        let profile = profile.control_points();
+        let mut rows = vec![profile.clone()];
+        for pair in stations.windows(2) {
+            let [(x0, t0), (x1, t1)] = [pair[0], pair[1]];
+            let reflect = |v: Vec3, axis: Vec3| {
+                let c = axis.length_squared();
+                if c > f32::EPSILON {
+                    v - (2. / c) * axis.dot(v) * axis
+                } else {
+                    v
+                }
+            };
+            let v1 = x1 - x0;
+            let (reference_l, tangent_l) = (reflect(reference, v1), reflect(t0, v1));
+            reference = reflect(reference_l, t1 - tangent_l).normalize();
+            let rotation = frame(t1, reference) * start_frame.transpose();
+            rows.push(
+                profile
+                    .iter()
+                    .map(|&p| x1 + rotation * (p - start))
+                    .collect(),
+            );
+        }
+
+        let sections = rows.into_iter().map(BezierCurve::new).collect::<Vec<_>>();
+        Self::loft(&sections)
+    }
+}
+
+/// Elevates curves to the highest degree among them.
+fn match_degrees<'a>(
+    curves: impl IntoIterator<Item = &'a BezierCurve<Vec3>>,
+) -> Vec<BezierCurve<Vec3>> {
+    let curves = curves.into_iter().collect::<Vec<_>>();
+    let degree = curves
+        .iter()
+        .map(|curve| curve.control_points().len() - 1)
+        .max()
+        .unwrap_or(0);
+    curves
+        .into_iter()
+        .map(|curve| curve.elevate_to(degree))
+        .collect()
+}
+
+/// Clamped knots of a single Bézier span of the given degree.
+fn bezier_knots(degree: usize) -> Vec<f32> {
+    [vec![0.; degree + 1], vec![1.; degree + 1]].concat()
+}
+
+/// Values at `t` of the B-spline basis functions of the given degree over the clamped `knots`,
+/// one for each control point.
+fn bspline_basis(knots: &[f32], degree: usize, t: f32) -> Vec<f64> {
+    let count = knots.len() - degree - 1;
+    let knots = knots.iter().map(|&k| k as f64).collect::<Vec<_>>();
+    let t = t as f64;
+    // Cox-de Boor recursion, from the degree 0 function of the span holding `t`, the last span
+    // holding the end of the domain
+    let span = (degree..count)
+        .rev()
+        .find(|&i| knots[i] <= t)
+        .unwrap_or(degree);
+    let mut basis = vec![0.; knots.len() - 1];
+    basis[span] = 1.;
+    for p in 1..=degree {
+        for i in 0..knots.len() - 1 - p {
+            let ramp = |a: usize, b: usize, x: f64| {
+                if knots[b] > knots[a] {
+                    x / (knots[b] - knots[a])
+                } else {
+                    0.
+                }
+            };
+            basis[i] = ramp(i, i + p, t - knots[i]) * basis[i]
+                + ramp(i + 1, i + p + 1, knots[i + p + 1] - t) * basis[i + 1];
+        }
+    }
+    basis.truncate(count);
+    basis
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+    use glam::{vec3, Vec3};
+    use test_log::test;
+
+    use crate::bezier::curve::BezierCurve;
+    use crate::bezier::nurbs::NurbsSurface;
+    use crate::bezier::parametric::ParametricSurface;
+    use crate::bezier::surface::BezierSurface;
+
+    #[test]
+    fn revolution_is_round_and_closed() {
+        let segment = BezierCurve::new([vec3(1., 0., 0.), vec3(1., 2., 0.)]);
+        let cylinder = NurbsSurface::revolution(&segment, Vec3::ZERO, Vec3::Y, TAU).unwrap();
+        assert_eq!(cylinder.degrees(), (1, 2));
+        assert!(cylinder.is_closed_v());
+        for k in 0..=20 {
+            let point = cylinder.get_point(0.5, k as f32 / 20.);
+            assert!((point.x.hypot(point.z) - 1.).abs() < 1e-5);
+            assert!((point.y - 1.).abs() < 1e-5);
+        }
+
+        for angle in [FRAC_PI_2, 1., PI, -4.] {
+            let arc = NurbsSurface::revolution(&segment, Vec3::ZERO, Vec3::Y, angle).unwrap();
+            let end = arc.get_point(0., 1.);
+            assert!((end - vec3(angle.cos(), 0., -angle.sin())).length() < 1e-5);
+            for k in 0..=200 {
+                let point = arc.get_point(0.5, k as f32 / 200.);
+                assert!((point.x.hypot(point.z) - 1.).abs() < 1e-5);
+            }
+        }
+        assert!(NurbsSurface::revolution(&segment, Vec3::ZERO, Vec3::Y, 0.).is_err());
+        assert!(NurbsSurface::revolution(&segment, Vec3::ZERO, Vec3::Y, 7.).is_err());
+    }
+
+    #[test]
+    fn loft_interpolates_sections() {
+        let section = |z: f32, bump: f32| {
+            BezierCurve::new([vec3(0., 0., z), vec3(1., bump, z), vec3(2., 0., z)])
+        };
+        let line = BezierCurve::new([vec3(0., 0., 1.), vec3(2., 0., 1.)]);
+        let surface = NurbsSurface::loft(&[section(0., 1.), line, section(2., -1.)]).unwrap();
+        assert_eq!(surface.degrees(), (2, 2));
+        for u in [0., 0.25, 0.5, 1.] {
+            let expected = vec3(2. * u, 0., 1.);
+            assert!((surface.get_point(u, 0.5) - expected).length() < 1e-5);
+        }
+
+        let extrusion = BezierSurface::extrusion(&section(0., 1.), Vec3::Z).unwrap();
+        let sweep = NurbsSurface::sweep(
+            &section(0., 1.),
+            &BezierCurve::new([Vec3::ZERO, Vec3::Z]),
+            3,
+        )
+        .unwrap();
+        for (u, v) in [(0., 0.), (0.3, 0.6), (1., 1.)] {
+            assert!((extrusion.get_point(u, v) - sweep.get_point(u, v)).length() < 1e-5);
+        }
+
+        // Evenly spaced sections, waving back and forth
+        let sections = (0..8)
+            .map(|k| section(k as f32, if k % 2 == 0 { 1. } else { -1. }))
+            .collect::<Vec<_>>();
+        let surface = NurbsSurface::loft(&sections).unwrap();
+        assert_eq!(surface.degrees(), (2, 3));
+        for (k, section) in sections.iter().enumerate() {
+            for u in [0., 0.3, 0.5, 1.] {
+                let point = surface.get_point(u, k as f32 / 7.);
+                assert!((point - section.get_point(u)).length() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn long_sweeps_stay_smooth() {
+        let profile = BezierCurve::new([vec3(-0.1, 0., 0.), vec3(0.1, 0., 0.)]);
+        let path = BezierCurve::new([
+            Vec3::ZERO,
+            vec3(0., 0., 2.),
+            vec3(2., 0., 2.),
+            vec3(2., 0., 4.),
+        ]);
+        let sweep = NurbsSurface::sweep(&profile, &path, 50).unwrap();
+        assert_eq!(sweep.degrees(), (1, 3));
+        assert!((sweep.get_point(0., 0.) - profile[0]).length() < 1e-6);
+        assert!((sweep.get_point(0.5, 1.) - path[3]).length() < 1e-5);
+        // The middle of the profile follows the path between the sections
+        let samples = (0..=1000)
+            .map(|k| path.get_point(k as f32 / 1000.))
+            .collect::<Vec<_>>();
+        for k in 0..=100 {
+            let point = sweep.get_point(0.5, k as f32 / 100.);
+            let distance = samples
+                .iter()
+                .map(|p| p.distance(point))
+                .fold(f32::INFINITY, f32::min);
+            assert!(distance < 1e-2);
+        }
+    }
+}
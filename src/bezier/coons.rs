@@ -0,0 +1,200 @@
+use glam::Vec3;
+
+use crate::bezier::curve::BezierCurve;
+use crate::bezier::patch::BezierPatch;
+use crate::bezier::surface::BezierSurface;
+
+/// Blending functions used to fill the interior of a [`CoonsPatch`] from its boundaries.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Blending {
+    /// Linear blending `t`; the surface is continuous across patches sharing boundaries.
+    Bilinear,
+    /// Cubic Hermite blending `3t² - 2t³`, which flattens the cross-boundary derivatives.
+    Bicubic,
+}
+
+impl Blending {
+    fn eval(self, t: f32) -> f32 {
+        match self {
+            Self::Bilinear => t,
+            Self::Bicubic => t * t * (3. - 2. * t),
+        }
+    }
+
+    /// Bézier coefficients of the blending function.
+    fn control_points(self) -> &'static [f32] {
+        match self {
+            Self::Bilinear => &[0., 1.],
+            Self::Bicubic => &[0., 0., 1., 1.],
+        }
+    }
+}
+
+/// Surface filling the area between four boundary curves.
+///
+/// `bottom` and `top` run along `u` at `v = 0` and `v = 1`, `left` and `right` run along `v`
+/// at `u = 0` and `u = 1`.
+#[derive(Debug, Clone)]
+pub struct CoonsPatch {
+    bottom: BezierCurve<Vec3>,
+    top: BezierCurve<Vec3>,
+    left: BezierCurve<Vec3>,
+    right: BezierCurve<Vec3>,
+    blending: Blending,
+}
+
+impl CoonsPatch {
+    pub fn new(
+        bottom: BezierCurve<Vec3>,
+        top: BezierCurve<Vec3>,
+        left: BezierCurve<Vec3>,
+        right: BezierCurve<Vec3>,
+    ) -> anyhow::Result<Self> {
+        const EPSILON: f32 = 1e-5;
+        let [bottom, top, left, right] = [bottom, top, left, right].map(|curve| curve.closed());
+        for (name, curve) in [
+            ("bottom", &bottom),
+            ("top", &top),
+            ("left", &left),
+            ("right", &right),
+        ] {
+            anyhow::ensure!(
+                curve.control_points().len() >= 2,
+                "Boundary curve {} needs at least 2 control points",
+                name
+            );
+        }
+        for (corner, a, b) in [
+            ("(0, 0)", bottom.get_point(0.), left.get_point(0.)),
+            ("(1, 0)", bottom.get_point(1.), right.get_point(0.)),
+            ("(0, 1)", top.get_point(0.), left.get_point(1.)),
+            ("(1, 1)", top.get_point(1.), right.get_point(1.)),
+        ] {
+            anyhow::ensure!(
+                a.distance(b) <= EPSILON,
+                "Boundary curves do not meet at corner {}",
+                corner
+            );
+        }
+        Ok(Self {
+            bottom,
+            top,
+            left,
+            right,
+            blending: Blending::Bilinear,
+        })
+    }
+
+    pub fn with_blending(mut self, blending: Blending) -> Self {
+        self.blending = blending;
+        self
+    }
+
+    pub fn get_point(&self, u: f32, v: f32) -> Vec3 {
+        let (fu, fv) = (self.blending.eval(u), self.blending.eval(v));
+        let [p00, p10, p01, p11] = self.corners();
+        let ruled_u = self.bottom.get_point(u).lerp(self.top.get_point(u), fv);
+        let ruled_v = self.left.get_point(v).lerp(self.right.get_point(v), fu);
+        let bilinear = p00.lerp(p10, fu).lerp(p01.lerp(p11, fu), fv);
+        ruled_u + ruled_v - bilinear
+    }
+
+    /// Exact tensor-product form of the patch.
+    ///
+    /// Coons patches with polynomial boundaries and blending functions are always polynomial;
+    /// the result has the degree of the boundaries, or of the blending functions if higher.
+    pub fn to_patch(&self) -> BezierPatch {
+        let blend = self.blending.control_points();
+        let degree = |a: &BezierCurve<Vec3>, b: &BezierCurve<Vec3>| {
+            (a.control_points().len().max(b.control_points().len())).max(blend.len()) - 1
+        };
+        let (deg_u, deg_v) = (
+            degree(&self.bottom, &self.top),
+            degree(&self.left, &self.right),
+        );
+
+        let ruled = |a: &BezierCurve<Vec3>, b: &BezierCurve<Vec3>, degree: usize| {
+            let (a, b) = (
+                a.elevate_to(degree).control_points(),
+                b.elevate_to(degree).control_points(),
+            );
+            let rows = blend
+                .iter()
+                .map(|&f| a.iter().zip(&b).map(move |(&a, &b)| a.lerp(b, f)));
+            BezierPatch::from_rows(rows).unwrap()
+        };
+        let ruled_u = ruled(&self.bottom, &self.top, deg_u).elevate_v_to(deg_v);
+        let ruled_v = ruled(&self.left, &self.right, deg_v)
+            .transpose()
+            .elevate_u_to(deg_u);
+        let [p00, p10, p01, p11] = self.corners();
+        let bilinear = BezierPatch::from_rows(blend.iter().map(|&fv| {
+            blend
+                .iter()
+                .map(move |&fu| p00.lerp(p10, fu).lerp(p01.lerp(p11, fu), fv))
+        }))
+        .unwrap()
+        .elevate_u_to(deg_u)
+        .elevate_v_to(deg_v);
+
+        let points = ruled_u
+            .points()
+            .iter()
+            .zip(ruled_v.points())
+            .zip(bilinear.points())
+            .map(|((&a, &b), &c)| a + b - c);
+        BezierPatch::new(deg_u + 1, deg_v + 1, points).unwrap()
+    }
+
+    fn corners(&self) -> [Vec3; 4] {
+        [
+            self.bottom.get_point(0.),
+            self.bottom.get_point(1.),
+            self.top.get_point(0.),
+            self.top.get_point(1.),
+        ]
+    }
+}
+
+impl From<&CoonsPatch> for BezierSurface {
+    fn from(coons: &CoonsPatch) -> Self {
+        Self::from(coons.to_patch())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Vec3};
+    use test_log::test;
+
+    use super::{Blending, CoonsPatch};
+    use crate::bezier::curve::BezierCurve;
+
+    #[test]
+    fn tensor_form_matches_evaluation() {
+        let bottom = BezierCurve::new([Vec3::ZERO, vec3(1., 1., 0.), vec3(2., 0., 0.)]);
+        let top = BezierCurve::new([vec3(0., 0., 2.), vec3(2., 0., 2.)]);
+        let left = BezierCurve::new([Vec3::ZERO, vec3(-0.5, 0.5, 1.), vec3(0., 0., 2.)]);
+        let right = BezierCurve::new([
+            vec3(2., 0., 0.),
+            vec3(2.5, -1., 0.7),
+            vec3(2., 1., 1.4),
+            vec3(2., 0., 2.),
+        ]);
+        let coons = CoonsPatch::new(bottom.clone(), top, left, right.clone()).unwrap();
+        for blending in [Blending::Bilinear, Blending::Bicubic] {
+            let coons = coons.clone().with_blending(blending);
+            let patch = coons.to_patch();
+            for (u, v) in [(0., 0.), (0.3, 0.2), (0.5, 0.5), (0.9, 0.7), (1., 1.)] {
+                assert!((patch.get_point(u, v) - coons.get_point(u, v)).length() < 1e-5);
+            }
+            for t in [0.1, 0.6] {
+                assert!((coons.get_point(t, 0.) - bottom.get_point(t)).length() < 1e-5);
+                assert!((coons.get_point(1., t) - right.get_point(t)).length() < 1e-5);
+            }
+        }
+
+        let disjoint = BezierCurve::new([Vec3::ONE, Vec3::X]);
+        assert!(CoonsPatch::new(bottom, disjoint.clone(), disjoint.clone(), disjoint).is_err());
+    }
+}
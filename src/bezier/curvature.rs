@@ -0,0 +1,157 @@
+use glam::{vec2, Mat2, Vec2, Vec3};
+
+use crate::bezier::patch::SurfaceDerivatives;
+
+/// Curvature of a surface at a point, measured with respect to its normal: curvatures are
+/// positive where the surface bends toward the normal.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Curvature {
+    /// Largest principal curvature.
+    pub k1: f32,
+    /// Smallest principal curvature.
+    pub k2: f32,
+    /// Unit tangent direction of `k1`.
+    pub dir1: Vec3,
+    /// Unit tangent direction of `k2`, completing `dir1` and the normal into a right-handed frame.
+    pub dir2: Vec3,
+    /// Product of the principal curvatures.
+    pub gaussian: f32,
+    /// Mean of the principal curvatures.
+    pub mean: f32,
+}
+
+impl Curvature {
+    /// Curvature from the first and second fundamental forms given by the partial derivatives
+    /// and the unit normal.
+    ///
+    /// Where the parameterization is degenerate the curvature is reported as zero.
+    pub fn new(derivatives: &SurfaceDerivatives, normal: Vec3) -> Self {
+        let SurfaceDerivatives {
+            du,
+            dv,
+            duu,
+            duv,
+            dvv,
+            ..
+        } = *derivatives;
+        let first = Mat2::from_cols_array(&[du.dot(du), du.dot(dv), du.dot(dv), dv.dot(dv)]);
+        let second = Mat2::from_cols_array(&[
+            duu.dot(normal),
+            duv.dot(normal),
+            duv.dot(normal),
+            dvv.dot(normal),
+        ]);
+        let metric = first.determinant();
+        let fallback = du
+            .reject_from(normal)
+            .try_normalize()
+            .unwrap_or_else(|| normal.any_orthonormal_vector());
+        if metric <= f32::EPSILON * first.x_axis.x.max(first.y_axis.y).powi(2) {
+            return Self::flat(fallback, normal);
+        }
+
+        let [e, f, _, g] = first.to_cols_array();
+        let [l, m, _, n] = second.to_cols_array();
+        let gaussian = second.determinant() / metric;
+        let mean = (e * n - 2. * f * m + g * l) / (2. * metric);
+        let spread = (mean * mean - gaussian).max(0.).sqrt();
+        let (k1, k2) = (mean + spread, mean - spread);
+
+        // Kernel of `II - k1 I`, taking the better conditioned of its two rows
+        let rows = [vec2(m - k1 * f, k1 * e - l), vec2(n - k1 * g, k1 * f - m)];
+        let coefficients = if rows[0].length_squared() >= rows[1].length_squared() {
+            rows[0]
+        } else {
+            rows[1]
+        };
+        let dir1 = (coefficients.x * du + coefficients.y * dv)
+            .reject_from_normalized(normal)
+            .try_normalize()
+            // Umbilic point: every direction is principal
+            .unwrap_or(fallback);
+        Self {
+            k1,
+            k2,
+            dir1,
+            dir2: normal.cross(dir1),
+            gaussian,
+            mean,
+        }
+    }
+
+    /// Gaussian and mean curvature packed as a vertex attribute.
+    pub fn attribute(&self) -> Vec2 {
+        vec2(self.gaussian, self.mean)
+    }
+
+    /// Same curvature measured against the opposite normal.
+    pub fn flipped(&self) -> Self {
+        Self {
+            k1: -self.k2,
+            k2: -self.k1,
+            dir1: self.dir2,
+            dir2: self.dir1,
+            mean: -self.mean,
+            ..*self
+        }
+    }
+
+    fn flat(dir1: Vec3, normal: Vec3) -> Self {
+        Self {
+            k1: 0.,
+            k2: 0.,
+            dir1,
+            dir2: normal.cross(dir1),
+            gaussian: 0.,
+            mean: 0.,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Vec3};
+    use test_log::test;
+
+    use crate::bezier::nurbs::NurbsSurface;
+    use crate::bezier::parametric::ParametricSurface;
+    use crate::bezier::patch::BezierPatch;
+    use crate::bezier::surface::BezierSurface;
+
+    #[test]
+    fn principal_curvatures() {
+        // z = x y, a saddle with K = -1 and H = 0 at the origin
+        let saddle = BezierSurface::from(
+            BezierPatch::from_rows([
+                [vec3(-1., -1., 1.), vec3(1., -1., -1.)],
+                [vec3(-1., 1., -1.), vec3(1., 1., 1.)],
+            ])
+            .unwrap(),
+        );
+        let curvature = saddle.curvature(0.5, 0.5);
+        assert!((curvature.gaussian + 1.).abs() < 1e-4);
+        assert!(curvature.mean.abs() < 1e-4);
+        assert!((curvature.k1 - 1.).abs() < 1e-4 && (curvature.k2 + 1.).abs() < 1e-4);
+        // Principal directions of z = x y are the diagonals
+        assert!((curvature.dir1.x.abs() - curvature.dir1.y.abs()).abs() < 1e-4);
+        assert!(curvature.dir1.dot(curvature.dir2).abs() < 1e-5);
+
+        let sphere = NurbsSurface::sphere(Vec3::ZERO, 2.);
+        for (u, v) in [(0.3, 0.4), (0.8, 0.6)] {
+            let curvature = sphere.curvature(u, v);
+            assert!((curvature.gaussian - 0.25).abs() < 1e-3);
+            assert!((curvature.mean.abs() - 0.5).abs() < 1e-3);
+        }
+
+        let cylinder = NurbsSurface::cylinder(Vec3::ZERO, 2., 1.);
+        let curvature = cylinder.curvature(0.3, 0.5);
+        assert!(curvature.gaussian.abs() < 1e-4);
+        assert!((curvature.mean.abs() - 0.25).abs() < 1e-3);
+        let straight = if curvature.k1.abs() < curvature.k2.abs() {
+            curvature.dir1
+        } else {
+            curvature.dir2
+        };
+        assert!(straight.dot(Vec3::Y).abs() > 1. - 1e-3);
+    }
+}
@@ -1,6 +1,6 @@
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Mul, Sub};
 
-use glam::{Vec2, Vec3};
+use glam::{Vec2, Vec3, Vec4};
 
 #[derive(Debug, Clone)]
 pub struct BezierCurve<V> {
@@ -34,27 +34,90 @@ impl<V> BezierCurve<V> {
         self.looping = v;
         self
     }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
 }
 
 impl<V: Copy> BezierCurve<V> {
-    pub fn get_point<F: Copy>(&self, s: F) -> V
+    /// Point of the curve at `s`, evaluated from [`Self::control_points`].
+    pub fn get_point(&self, s: f32) -> V
     where
-        V: Lerp<F>,
+        V: Lerp<f32>,
     {
-        let mut points = self
-            .points
-            .windows(2)
-            .map(|v| v[0].lerp(v[1], s))
-            .collect::<Vec<_>>();
-        if points.len() == 1 {
-            points.remove(0)
-        } else {
-            Self {
-                points,
-                looping: false,
+        let mut points = self.control_points();
+        for len in (1..points.len()).rev() {
+            for k in 0..len {
+                points[k] = points[k].lerp(points[k + 1], s);
             }
-            .get_point(s)
         }
+        points[0]
+    }
+
+    /// Open curve with the control points used for evaluation, see [`Self::control_points`].
+    pub fn closed(&self) -> Self
+    where
+        V: Lerp<f32>,
+    {
+        Self::new(self.control_points())
+    }
+
+    /// Control points used for evaluation.
+    ///
+    /// Looping curves are closed by appending `2 P0 - P1` and `P0`, which makes the end of the
+    /// curve meet its start with the same tangent.
+    pub fn control_points(&self) -> Vec<V>
+    where
+        V: Lerp<f32>,
+    {
+        let mut points = self.points.clone();
+        if let (true, &[p0, p1, ..]) = (self.looping, &self.points[..]) {
+            points.extend([p1.lerp(p0, 2.), p0]);
+        }
+        points
+    }
+
+    /// Same curve with one more control point.
+    pub fn elevate(&self) -> Self
+    where
+        V: Lerp<f32>,
+    {
+        let points = self.control_points();
+        let n = points.len() as f32;
+        let mut elevated = Vec::with_capacity(points.len() + 1);
+        elevated.extend(points.first());
+        elevated.extend(
+            points
+                .windows(2)
+                .enumerate()
+                .map(|(i, w)| w[1].lerp(w[0], (i + 1) as f32 / n)),
+        );
+        elevated.extend(points.last());
+        Self::new(elevated)
+    }
+
+    /// Same curve, elevated until it has the given degree; curves of higher degree are returned
+    /// unchanged.
+    pub fn elevate_to(&self, degree: usize) -> Self
+    where
+        V: Lerp<f32>,
+    {
+        let mut curve = Self::new(self.control_points());
+        while curve.len() <= degree {
+            curve = curve.elevate();
+        }
+        curve
+    }
+
+    /// Hodograph of the curve, whose points are the derivatives of this curve.
+    pub fn derivative(&self) -> Self
+    where
+        V: Lerp<f32> + Sub<Output = V> + Mul<f32, Output = V>,
+    {
+        let points = self.control_points();
+        let n = points.len().saturating_sub(1) as f32;
+        Self::new(points.windows(2).map(|w| (w[1] - w[0]) * n))
     }
 }
 
@@ -74,6 +137,12 @@ impl Lerp<f32> for Vec3 {
     }
 }
 
+impl Lerp<f32> for Vec4 {
+    fn lerp(self, other: Self, s: f32) -> Self {
+        Vec4::lerp(self, other, s)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use glam::{vec2, Vec2};
@@ -88,4 +157,36 @@ mod tests {
         assert_eq!(curve.get_point(1.), Vec2::X);
         assert_eq!(curve.get_point(0.5), vec2(0.5, 0.0));
     }
+
+    #[test]
+    fn looping_curve() {
+        let curve = BezierCurve::new([Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y]).looping(true);
+        assert_eq!(curve.control_points().len(), 6);
+        assert_eq!(curve.get_point(1.), Vec2::ZERO);
+        let h = 1e-3;
+        let start = (curve.get_point(h) - curve.get_point(0.)) / h;
+        let end = (curve.get_point(1.) - curve.get_point(1. - h)) / h;
+        assert!((start - end).length() < 1e-1);
+        let derivative = curve.derivative();
+        let closed = curve.closed();
+        assert!(!closed.is_looping());
+        for t in [0., 0.3, 0.8, 1.] {
+            assert_eq!(closed.get_point(t), curve.get_point(t));
+            let fd = (curve.get_point(t + h) - curve.get_point(t - h)) / (2. * h);
+            assert!((derivative.get_point(t) - fd).length() < 1e-1);
+        }
+    }
+
+    #[test]
+    fn elevation_and_derivative() {
+        let curve = BezierCurve::new([Vec2::ZERO, vec2(1., 2.), vec2(3., -1.)]);
+        let elevated = curve.elevate_to(4);
+        assert_eq!(elevated.len(), 5);
+        let derivative = curve.derivative();
+        for t in [0., 0.3, 0.8, 1.] {
+            assert!((elevated.get_point(t) - curve.get_point(t)).length() < 1e-5);
+            let fd = (curve.get_point(t + 1e-3) - curve.get_point(t - 1e-3)) / 2e-3;
+            assert!((derivative.get_point(t) - fd).length() < 1e-2);
+        }
+    }
 }
@@ -0,0 +1,171 @@
+use std::fmt;
+use std::sync::Arc;
+
+use glam::{vec2, Vec2, Vec3};
+use image::DynamicImage;
+
+use crate::mesh::MeshData;
+
+/// Height field moving tessellated vertices along the surface normal.
+///
+/// Heights are looked up at the texture coordinates of the vertices, so that a height map lines
+/// up with the other textures of the material.
+#[derive(Clone)]
+pub struct Displacement {
+    height: Arc<dyn Fn(Vec2) -> f32 + Send + Sync>,
+    scale: f32,
+}
+
+impl fmt::Debug for Displacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Displacement")
+            .field("scale", &self.scale)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Displacement {
+    pub fn new(height: impl Fn(Vec2) -> f32 + Send + Sync + 'static) -> Self {
+        Self {
+            height: Arc::new(height),
+            scale: 1.,
+        }
+    }
+
+    /// Height map from the luminance of an image, in `[0, 1]` before scaling, sampled bilinearly
+    /// and repeated outside of `[0, 1]²` like textures.
+    pub fn from_image(image: &DynamicImage) -> Self {
+        let image = image.to_luma32f();
+        let (width, height) = image.dimensions();
+        let texels = image.into_raw();
+        let texel = move |x: i64, y: i64| {
+            let x = x.rem_euclid(width as i64) as usize;
+            let y = y.rem_euclid(height as i64) as usize;
+            texels[y * width as usize + x]
+        };
+        Self::new(move |uv| {
+            // Texture coordinates start at the bottom row of the image
+            let position = vec2(uv.x * width as f32, (1. - uv.y) * height as f32) - 0.5;
+            let (base, t) = (position.floor(), position - position.floor());
+            let (x, y) = (base.x as i64, base.y as i64);
+            let bottom = texel(x, y) + t.x * (texel(x + 1, y) - texel(x, y));
+            let top = texel(x, y + 1) + t.x * (texel(x + 1, y + 1) - texel(x, y + 1));
+            bottom + t.y * (top - bottom)
+        })
+    }
+
+    /// Multiplies the heights, in world units.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn height(&self, uv: Vec2) -> f32 {
+        self.scale * (self.height)(uv)
+    }
+
+    /// Moves the vertices along their normal, then recomputes normals and tangents from the
+    /// displaced triangles.
+    ///
+    /// Heights are measured along the surface normal, so the normals of a `flipped` mesh are
+    /// turned back for the offset; its reversed winding keeps the recomputed normals flipped.
+    ///
+    /// Copies of a vertex along seams all take the height of the first one, so that the mesh
+    /// does not crack where texture coordinates jump.
+    pub(crate) fn apply(&self, mesh: &mut MeshData, flipped: bool) {
+        let group = mesh.position_groups();
+        let side = if flipped { -1. } else { 1. };
+        let offsets = mesh
+            .vertices
+            .iter()
+            .map(|vertex| side * self.height(vertex.uv) * vertex.normal)
+            .collect::<Vec<_>>();
+        for (vertex, &g) in mesh.vertices.iter_mut().zip(&group) {
+            vertex.position += offsets[g];
+        }
+
+        // Area-weighted face normals, shared by the copies of each vertex
+        let mut normals = vec![Vec3::ZERO; mesh.vertices.len()];
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize);
+            let [pa, pb, pc] = [a, b, c].map(|i| mesh.vertices[i].position);
+            let normal = (pb - pa).cross(pc - pa);
+            for i in [a, b, c] {
+                normals[group[i]] += normal;
+            }
+        }
+        for (vertex, &g) in mesh.vertices.iter_mut().zip(&group) {
+            if let Some(normal) = normals[g].try_normalize() {
+                vertex.normal = normal;
+                vertex.tangent = vertex
+                    .tangent
+                    .reject_from_normalized(normal)
+                    .normalize_or_zero();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec2, vec3, Vec3};
+    use image::{DynamicImage, GrayImage};
+    use test_log::test;
+
+    use super::Displacement;
+    use crate::bezier::nurbs::NurbsSurface;
+    use crate::bezier::patch::BezierPatch;
+    use crate::bezier::surface::BezierSurface;
+    use crate::bezier::tessellation::TessellationBuilder;
+
+    #[test]
+    fn displaces_along_normals() {
+        let plane = BezierSurface::from(
+            BezierPatch::from_rows([
+                [Vec3::ZERO, vec3(0., 0., 2.)],
+                [vec3(2., 0., 0.), vec3(2., 0., 2.)],
+            ])
+            .unwrap(),
+        );
+        let normal = plane.normal(0.5, 0.5);
+        // Ramp rising along `u`
+        let ramp = Displacement::new(|uv| uv.x).with_scale(0.5);
+        let du = plane.derivatives(0.5, 0.5).du;
+        let tilted = (normal - 0.5 * du / du.length_squared()).normalize();
+        // Flipping the mesh turns its normals around but keeps the heights on the same side
+        for (flipped, side) in [(false, 1.), (true, -1.)] {
+            let mesh = TessellationBuilder::new(5, 5)
+                .flipped(flipped)
+                .with_displacement(ramp.clone())
+                .build(&plane)
+                .unwrap();
+            for vertex in &mesh.vertices {
+                let height = vertex.position.dot(normal);
+                assert!((height - 0.5 * vertex.uv.x).abs() < 1e-5);
+                assert!(vertex.normal.dot(side * tilted) > 1. - 1e-4);
+                assert!(vertex.tangent.dot(vertex.normal).abs() < 1e-5);
+            }
+        }
+
+        // A tube pushed outward stays closed along its seam, even when heights differ there
+        let tube = NurbsSurface::cylinder(Vec3::ZERO, 1., 1.);
+        let mesh = TessellationBuilder::new(32, 4)
+            .with_displacement(Displacement::new(|uv| 0.1 + uv.x).with_scale(0.2))
+            .build(&tube)
+            .unwrap();
+        let group = mesh.position_groups();
+        let distinct = (0..group.len()).filter(|&i| group[i] == i).count();
+        assert_eq!(distinct, 31 * 4);
+        for vertex in &mesh.vertices {
+            let radius = vec3(vertex.position.x, 0., vertex.position.z).length();
+            assert!(radius > 1.01);
+        }
+
+        // Image heights are interpolated between texel centers and wrap around
+        let image = GrayImage::from_raw(2, 1, vec![0, 255]).unwrap();
+        let map = Displacement::from_image(&DynamicImage::ImageLuma8(image)).with_scale(2.);
+        for (u, height) in [(0.25, 0.), (0.5, 1.), (0.75, 2.), (1., 1.), (1.25, 0.)] {
+            assert!((map.height(vec2(u, 0.5)) - height).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use anyhow::Context;
+use glam::{vec2, DVec3, Mat3, Vec2, Vec3};
+
+use crate::bezier::basis::bernstein;
+use crate::bezier::linalg;
+use crate::bezier::patch::BezierPatch;
+use crate::bezier::surface::BezierSurface;
+
+/// Rounds of reprojecting the points onto the fitted surface and fitting again.
+const PARAMETER_CORRECTIONS: usize = 3;
+const POWER_ITERATIONS: usize = 64;
+
+/// Surface constructors from measured points.
+impl BezierSurface {
+    /// Surface of the given degree fitted to a `width` × `height` grid of points, stored row-major
+    /// with rows along `u` as in [`BezierPatch`].
+    ///
+    /// Points are placed along each direction by chord length, averaged over the grid lines. The
+    /// surface interpolates the grid when its degree is one less than the grid size, and fits it
+    /// in the least squares sense when lower.
+    pub fn fit_grid(
+        width: usize,
+        height: usize,
+        points: &[Vec3],
+        (degree_u, degree_v): (usize, usize),
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            points.len() == width * height,
+            "Grid of size {}x{} needs {} points, got {}",
+            width,
+            height,
+            width * height,
+            points.len()
+        );
+        anyhow::ensure!(
+            degree_u < width && degree_v < height,
+            "Degree ({}, {}) is too high for a {}x{} grid",
+            degree_u,
+            degree_v,
+            width,
+            height
+        );
+        let grid = BezierPatch::new(width, height, points.iter().copied())?;
+        let params_u = chord_lengths(grid.rows().map(|row| row.to_vec()))?;
+        let params_v = chord_lengths((0..width).map(|i| grid.column(i).collect()))?;
+
+        // Least squares on a grid separates along each direction
+        let rows = grid
+            .rows()
+            .map(|row| fit_curve(row, &params_u, degree_u))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .context("Cannot fit grid rows")?;
+        let mut control = vec![Vec3::ZERO; (degree_u + 1) * (degree_v + 1)];
+        for i in 0..=degree_u {
+            let column = rows.iter().map(|row| row[i]).collect::<Vec<_>>();
+            let fitted =
+                fit_curve(&column, &params_v, degree_v).context("Cannot fit grid columns")?;
+            for (j, point) in fitted.into_iter().enumerate() {
+                control[j * (degree_u + 1) + i] = point;
+            }
+        }
+        BezierPatch::new(degree_u + 1, degree_v + 1, control).map(Self::from)
+    }
+
+    /// Surface of the given degree fitted to points at known parameters, minimizing the mean
+    /// squared distance plus `smoothing` times the bending energy of the control net.
+    pub fn fit_parameterized(
+        points: &[Vec3],
+        params: &[Vec2],
+        (degree_u, degree_v): (usize, usize),
+        smoothing: f32,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            points.len() == params.len(),
+            "Got {} points but {} parameters",
+            points.len(),
+            params.len()
+        );
+        anyhow::ensure!(!points.is_empty(), "No points to fit");
+        let (width, height) = (degree_u + 1, degree_v + 1);
+        let n = width * height;
+        let index = |i: usize, j: usize| j * width + i;
+
+        let mut matrix = vec![0f64; n * n];
+        let mut rhs = vec![DVec3::ZERO; n];
+        let weight = 1. / points.len() as f64;
+        let mut basis = vec![0f64; n];
+        for (point, param) in points.iter().zip(params) {
+            for j in 0..height {
+                for i in 0..width {
+                    basis[index(i, j)] = bernstein(degree_u, i, param.x as f64)
+                        * bernstein(degree_v, j, param.y as f64);
+                }
+            }
+            for (row, &b) in basis.iter().enumerate() {
+                rhs[row] += weight * b * point.as_dvec3();
+                for (col, &c) in basis.iter().enumerate() {
+                    matrix[row * n + col] += weight * b * c;
+                }
+            }
+        }
+
+        // Second differences of the control net along `u`, `v`, and the twist
+        let mut stencils = Vec::new();
+        for j in 0..height {
+            for i in 1..width.saturating_sub(1) {
+                stencils.push(vec![
+                    (index(i - 1, j), 1.),
+                    (index(i, j), -2.),
+                    (index(i + 1, j), 1.),
+                ]);
+            }
+        }
+        for i in 0..width {
+            for j in 1..height.saturating_sub(1) {
+                stencils.push(vec![
+                    (index(i, j - 1), 1.),
+                    (index(i, j), -2.),
+                    (index(i, j + 1), 1.),
+                ]);
+            }
+        }
+        for j in 0..height - 1 {
+            for i in 0..width - 1 {
+                let twist = std::f64::consts::SQRT_2;
+                stencils.push(vec![
+                    (index(i, j), twist),
+                    (index(i + 1, j), -twist),
+                    (index(i, j + 1), -twist),
+                    (index(i + 1, j + 1), twist),
+                ]);
+            }
+        }
+        for stencil in stencils {
+            for &(row, a) in &stencil {
+                for &(col, b) in &stencil {
+                    matrix[row * n + col] += smoothing as f64 * a * b;
+                }
+            }
+        }
+
+        linalg::solve(&mut matrix, &mut rhs).context("Not enough points to fit the surface")?;
+        BezierPatch::new(width, height, rhs.into_iter().map(|p| p.as_vec3())).map(Self::from)
+    }
+
+    /// Surface of the given degree fitted to scattered points, see [`Self::fit_parameterized`].
+    ///
+    /// Points are first parameterized by projection onto their best fitting plane, then by
+    /// projection onto the fitted surface over a few rounds of refitting. The points should lie
+    /// over a region which is a graph over that plane.
+    pub fn fit_scattered(
+        points: &[Vec3],
+        degree: (usize, usize),
+        smoothing: f32,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(!points.is_empty(), "No points to fit");
+        let center = points.iter().fold(Vec3::ZERO, |sum, &p| sum + p) / points.len() as f32;
+        let (axis_u, axis_v) = principal_axes(points, center);
+        let planar = points
+            .iter()
+            .map(|&p| vec2((p - center).dot(axis_u), (p - center).dot(axis_v)))
+            .collect::<Vec<_>>();
+        let (min, max) = planar.iter().fold(
+            (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |(min, max), &p| (min.min(p), max.max(p)),
+        );
+        let extent = (max - min).max(Vec2::splat(f32::EPSILON));
+        let mut params = planar
+            .into_iter()
+            .map(|p| (p - min) / extent)
+            .collect::<Vec<_>>();
+
+        let mut surface = Self::fit_parameterized(points, &params, degree, smoothing)?;
+        for _ in 0..PARAMETER_CORRECTIONS {
+            for (param, &point) in params.iter_mut().zip(points) {
+                let (u, v, _) = surface.project(point);
+                *param = vec2(u, v);
+            }
+            surface = Self::fit_parameterized(points, &params, degree, smoothing)?;
+        }
+        Ok(surface)
+    }
+}
+
+/// Control points of the Bézier curve of the given degree closest to the points at the given
+/// parameters, in the least squares sense.
+fn fit_curve(points: &[Vec3], params: &[f32], degree: usize) -> anyhow::Result<Vec<Vec3>> {
+    let n = degree + 1;
+    let mut matrix = vec![0f64; n * n];
+    let mut rhs = vec![DVec3::ZERO; n];
+    for (point, &t) in points.iter().zip(params) {
+        let basis = (0..n)
+            .map(|i| bernstein(degree, i, t as f64))
+            .collect::<Vec<_>>();
+        for (row, &b) in basis.iter().enumerate() {
+            rhs[row] += b * point.as_dvec3();
+            for (col, &c) in basis.iter().enumerate() {
+                matrix[row * n + col] += b * c;
+            }
+        }
+    }
+    linalg::solve(&mut matrix, &mut rhs)?;
+    Ok(rhs.into_iter().map(|p| p.as_vec3()).collect())
+}
+
+/// Normalized cumulative chord lengths along polylines of equal length, averaged over them.
+fn chord_lengths(lines: impl Iterator<Item = Vec<Vec3>>) -> anyhow::Result<Vec<f32>> {
+    let mut params = Vec::new();
+    for line in lines {
+        params.resize(line.len(), 0.);
+        let mut length = 0.;
+        for (k, pair) in line.windows(2).enumerate() {
+            length += pair[0].distance(pair[1]);
+            params[k + 1] += length;
+        }
+    }
+    let total = *params.last().context("Empty grid")?;
+    if params.len() == 1 {
+        return Ok(vec![0.]);
+    }
+    anyhow::ensure!(
+        total > 0.,
+        "Grid points are all identical along a direction"
+    );
+    Ok(params.into_iter().map(|p| p / total).collect())
+}
+
+/// Two largest principal directions of the points around their center.
+fn principal_axes(points: &[Vec3], center: Vec3) -> (Vec3, Vec3) {
+    let covariance = points.iter().fold(Mat3::ZERO, |m, &p| {
+        let d = p - center;
+        m + Mat3::from_cols(d * d.x, d * d.y, d * d.z)
+    });
+    let dominant = |matrix: Mat3, start: Vec3| {
+        (0..POWER_ITERATIONS).fold(start, |v, _| (matrix * v).try_normalize().unwrap_or(v))
+    };
+    let start = [covariance.x_axis, covariance.y_axis, covariance.z_axis]
+        .into_iter()
+        .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+        .and_then(Vec3::try_normalize)
+        .unwrap_or(Vec3::X);
+    let first = dominant(covariance, start);
+    let deflated = covariance
+        - Mat3::from_cols(first * first.x, first * first.y, first * first.z)
+            * first.dot(covariance * first);
+    let second = dominant(deflated, first.any_orthonormal_vector());
+    let second = second.reject_from_normalized(first).normalize_or_zero();
+    (first, second)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec2, vec3};
+    use test_log::test;
+
+    use crate::bezier::patch::BezierPatch;
+    use crate::bezier::surface::BezierSurface;
+
+    fn saddle() -> BezierSurface {
+        BezierSurface::from(
+            BezierPatch::from_rows([
+                [vec3(0., 0., 0.), vec3(1., 0.5, 0.), vec3(2., 0., 0.)],
+                [vec3(0., -0.5, 1.), vec3(1., 0., 1.), vec3(2., -0.5, 1.)],
+                [vec3(0., 0., 2.), vec3(1., 0.5, 2.), vec3(2., 0., 2.)],
+            ])
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn grid_fitting() {
+        let surface = saddle();
+        let (width, height) = (7, 5);
+        let points = (0..height)
+            .flat_map(|j| (0..width).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                surface.get_point(
+                    i as f32 / (width - 1) as f32,
+                    j as f32 / (height - 1) as f32,
+                )
+            })
+            .collect::<Vec<_>>();
+        // Least squares recovers a surface of the same degree up to parameterization
+        let fitted = BezierSurface::fit_grid(width, height, &points, (2, 2)).unwrap();
+        for &point in &points {
+            assert!(fitted.project(point).2 < 1e-2);
+        }
+        // Interpolation goes through every point
+        let interpolated = BezierSurface::fit_grid(width, height, &points, (6, 4)).unwrap();
+        assert_eq!(interpolated.patch().degree(), (6, 4));
+        for &point in &points {
+            assert!(interpolated.project(point).2 < 1e-4);
+        }
+        assert!(BezierSurface::fit_grid(width, height, &points, (7, 2)).is_err());
+    }
+
+    #[test]
+    fn scattered_fitting() {
+        let surface = saddle();
+        let params = (0..200).map(|k| {
+            // Low-discrepancy samples of the unit square
+            let golden = 0.618_034;
+            vec2((k as f32 * golden).fract(), (k as f32 + 0.5) / 200.)
+        });
+        let points = params
+            .clone()
+            .map(|p| surface.get_point(p.x, p.y))
+            .collect::<Vec<_>>();
+
+        let exact =
+            BezierSurface::fit_parameterized(&points, &params.collect::<Vec<_>>(), (2, 2), 0.)
+                .unwrap();
+        for (a, b) in exact.patch().points().iter().zip(surface.patch().points()) {
+            assert!(a.distance(*b) < 1e-3);
+        }
+
+        let fitted = BezierSurface::fit_scattered(&points, (3, 3), 0.).unwrap();
+        let error = points
+            .iter()
+            .map(|&p| fitted.project(p).2)
+            .fold(0f32, f32::max);
+        assert!(error < 1e-2);
+
+        // Smoothing flattens the control net
+        let smooth = BezierSurface::fit_scattered(&points, (3, 3), 10.).unwrap();
+        let bending = |surface: &BezierSurface| {
+            let patch = surface.patch();
+            (0..4)
+                .map(|j| {
+                    let row = patch.row(j);
+                    (row[0] - 2. * row[1] + row[2]).length()
+                        + (row[1] - 2. * row[2] + row[3]).length()
+                })
+                .sum::<f32>()
+        };
+        assert!(bending(&smooth) < bending(&fitted));
+        assert!(smooth.get_point(0.5, 0.5).is_finite());
+    }
+}
@@ -0,0 +1,102 @@
+use glam::vec2;
+
+use crate::bezier::basis::GAUSS_LEGENDRE;
+use crate::bezier::parametric::ParametricSurface;
+use crate::mass::{MassProperties, Moments};
+
+/// Quadrature cells along each direction per unit of degree of each polynomial piece.
+const SEGMENTS_PER_DEGREE: usize = 2;
+
+/// Mass properties of the surface by Gauss–Legendre quadrature over its parameter domain.
+///
+/// Quadrature points outside of the trim are skipped, so trimmed surfaces are integrated only as
+/// accurately as the quadrature grid resolves the trimming curves.
+pub(crate) fn mass_properties<S: ParametricSurface + ?Sized>(surface: &S) -> MassProperties {
+    let (breaks_u, breaks_v) = surface.breaks();
+    let (degree_u, degree_v) = surface.degree();
+    let (cells_u, cells_v) = (cells(&breaks_u, degree_u), cells(&breaks_v, degree_v));
+    let mut moments = Moments::default();
+    for &(v0, hv) in &cells_v {
+        for &(u0, hu) in &cells_u {
+            for &(x, wx) in &GAUSS_LEGENDRE {
+                for &(y, wy) in &GAUSS_LEGENDRE {
+                    let param = vec2(u0 + (x + 1.) / 2. * hu, v0 + (y + 1.) / 2. * hv);
+                    if !surface.trim().iter().all(|trim| trim.contains(param)) {
+                        continue;
+                    }
+                    let derivatives = surface.derivatives(param.x, param.y);
+                    let weight = wx * wy * hu * hv / 4.;
+                    let normal = derivatives.du.cross(derivatives.dv);
+                    moments.add(derivatives.point, normal, weight);
+                }
+            }
+        }
+    }
+    moments.finish()
+}
+
+/// Start and size of quadrature cells tiling each piece between `breaks`, so that no cell
+/// straddles a join where the derivatives jump.
+fn cells(breaks: &[f32], degree: usize) -> Vec<(f32, f32)> {
+    let pieces = breaks.len() - 1;
+    let segments = SEGMENTS_PER_DEGREE * (degree / pieces).max(1);
+    breaks
+        .windows(2)
+        .flat_map(|pair| {
+            let h = (pair[1] - pair[0]) / segments as f32;
+            (0..segments).map(move |k| (pair[0] + k as f32 * h, h))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use glam::{vec3, Mat3, Vec3};
+    use test_log::test;
+
+    use crate::bezier::nurbs::NurbsSurface;
+    use crate::bezier::parametric::ParametricSurface;
+    use crate::bezier::tessellation::TessellationBuilder;
+
+    #[test]
+    fn sphere_mass_properties() {
+        let center = vec3(1., -2., 0.5);
+        let round = NurbsSurface::sphere(center, 2.);
+        // Moving the joins of the pieces changes the parameterization but not the shape
+        let (width, height) = (round.width(), round.height());
+        let grid = (0..height).flat_map(|j| (0..width).map(move |i| (i, j)));
+        let sphere = NurbsSurface::new(
+            2,
+            2,
+            vec![0., 0., 0., 0.1, 0.1, 0.45, 0.45, 0.6, 0.6, 1., 1., 1.],
+            vec![0., 0., 0., 0.3, 0.3, 1., 1., 1.],
+            width,
+            grid.clone().map(|(i, j)| round.point(i, j)),
+        )
+        .unwrap()
+        .with_weights(grid.map(|(i, j)| round.weight(i, j)))
+        .unwrap();
+        let exact = sphere.mass_properties();
+        let volume = 4. / 3. * PI * 8.;
+        assert!((exact.area - 16. * PI).abs() < 1e-3);
+        assert!((exact.volume.abs() - volume).abs() < 1e-3);
+        assert!((exact.centroid - center).length() < 1e-4);
+        // I = 2/5 m r² about any axis through the center
+        let inertia = Mat3::from_diagonal(Vec3::splat(0.4 * volume * 4.)) * exact.volume.signum();
+        assert!(exact.inertia.abs_diff_eq(inertia, 1e-2));
+
+        // The tessellation converges to the surface
+        let errors = [16, 64].map(|resolution| {
+            let mesh = TessellationBuilder::new(resolution, resolution)
+                .build(&round)
+                .unwrap()
+                .mass_properties();
+            assert!((mesh.centroid - center).length() < 1e-3);
+            (exact.volume - mesh.volume).abs() / volume
+        });
+        // Second order convergence: refining 4 times divides the error by about 16
+        assert!(errors[1] < errors[0] / 10. && errors[1] < 5e-3);
+    }
+}
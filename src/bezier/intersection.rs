@@ -0,0 +1,468 @@
+use std::collections::{HashMap, HashSet};
+
+use glam::{vec2, IVec3, Mat3, Mat4, Vec2, Vec3, Vec4};
+
+use crate::bezier::patch::{BezierPatch, SurfaceDerivatives};
+
+/// Subdivision depth at which overlapping sub-patches become seeds for marching.
+const MAX_DEPTH: usize = 8;
+const NEWTON_ITERATIONS: usize = 16;
+/// Upper bound on the number of marching steps in each direction from a seed.
+const MAX_STEPS: usize = 10_000;
+/// Sine of the angle under which the normals of sub-patches are considered parallel, where
+/// the patches touch tangentially or coincide.
+const PARALLEL: f32 = 1e-3;
+
+/// Intersection curve of two patches, as matching polylines in space and in the parameter domain
+/// of each patch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntersectionCurve {
+    pub points: Vec<Vec3>,
+    pub params_a: Vec<Vec2>,
+    pub params_b: Vec<Vec2>,
+    /// Whether the curve is a loop; its last point then connects back to the first.
+    pub closed: bool,
+}
+
+/// Point common to both patches, with its parameters on each.
+#[derive(Debug, Copy, Clone)]
+struct Sample {
+    point: Vec3,
+    a: Vec2,
+    b: Vec2,
+}
+
+impl BezierPatch {
+    /// Curves along which this patch meets `other`, traced with points about `step` apart.
+    ///
+    /// Pairs of sub-patches with overlapping bounding boxes are subdivided to find seed points,
+    /// from which the curves are marched in both directions until they leave either domain or
+    /// close into a loop. Curves ending on the boundary of a domain end exactly on it.
+    ///
+    /// Tangential contact is not traced, and neither are regions where the patches coincide.
+    pub fn intersect(&self, other: &BezierPatch, step: f32) -> Vec<IntersectionCurve> {
+        let (min, max) = self.bounding_box();
+        let tolerance = 1e-5 * (max - min).max_element().max(1.);
+        let mut curves: Vec<IntersectionCurve> = Vec::new();
+        // Points of the traced curves, bucketed by cells of size `step`
+        let mut traced = HashMap::<IVec3, Vec<Vec3>>::new();
+        for (a, b) in self.seeds(other, step, tolerance) {
+            let seed = match converge(self, other, a, b, tolerance) {
+                Some(seed) => seed,
+                None => continue,
+            };
+            let center = cell(seed.point, step);
+            let near = (-1..=1)
+                .flat_map(|x| {
+                    (-1..=1).flat_map(move |y| (-1..=1).map(move |z| IVec3::new(x, y, z)))
+                })
+                .filter_map(|offset| traced.get(&(center + offset)))
+                .flatten()
+                .any(|p| p.distance(seed.point) < step);
+            if near {
+                continue;
+            }
+            if let Some(curve) = march(self, other, seed, step, tolerance) {
+                for &point in &curve.points {
+                    traced.entry(cell(point, step)).or_default().push(point);
+                }
+                curves.push(curve);
+            }
+        }
+        curves
+    }
+
+    /// Parameter pairs at the centers of small overlapping sub-patches, at most one per cell of
+    /// size `step` in space.
+    fn seeds(&self, other: &BezierPatch, step: f32, tolerance: f32) -> Vec<(Vec2, Vec2)> {
+        let unit = [Vec2::ZERO, Vec2::ONE];
+        let mut stack = vec![(self.clone(), unit, other.clone(), unit, 0)];
+        let mut seeds = Vec::new();
+        let mut cells = HashSet::new();
+        while let Some((a, range_a, b, range_b, depth)) = stack.pop() {
+            let ((min_a, max_a), (min_b, max_b)) = (a.bounding_box(), b.bounding_box());
+            let disjoint = min_a.cmpgt(max_b).any() || min_b.cmpgt(max_a).any();
+            if disjoint || coplanar(&a, &b, tolerance) {
+                continue;
+            }
+            let (size_a, size_b) = ((max_a - min_a).max_element(), (max_b - min_b).max_element());
+            if depth == MAX_DEPTH || size_a.max(size_b) < step {
+                let (da, db) = (a.derivatives(0.5, 0.5), b.derivatives(0.5, 0.5));
+                let (na, nb) = (da.du.cross(da.dv), db.du.cross(db.dv));
+                let parallel = na
+                    .normalize_or_zero()
+                    .cross(nb.normalize_or_zero())
+                    .length()
+                    < PARALLEL;
+                if !parallel && cells.insert(cell(da.point, step)) {
+                    seeds.push((center(range_a), center(range_b)));
+                }
+            } else if size_a >= size_b {
+                for (quarter, range) in quarters(&a, range_a) {
+                    stack.push((quarter, range, b.clone(), range_b, depth + 1));
+                }
+            } else {
+                for (quarter, range) in quarters(&b, range_b) {
+                    stack.push((a.clone(), range_a, quarter, range, depth + 1));
+                }
+            }
+        }
+        seeds
+    }
+}
+
+/// Traces the intersection curve through `seed`, first forward then backward unless it closes.
+fn march(
+    a: &BezierPatch,
+    b: &BezierPatch,
+    seed: Sample,
+    step: f32,
+    tolerance: f32,
+) -> Option<IntersectionCurve> {
+    let (mut forward, closed) = trace(a, b, seed, step, tolerance, 1.)?;
+    if !closed {
+        let (backward, _) = trace(a, b, seed, step, tolerance, -1.)?;
+        forward.splice(0..0, backward.into_iter().skip(1).rev());
+    }
+    Some(IntersectionCurve {
+        points: forward.iter().map(|s| s.point).collect(),
+        params_a: forward.iter().map(|s| s.a).collect(),
+        params_b: forward.iter().map(|s| s.b).collect(),
+        closed,
+    })
+}
+
+/// Samples from `seed` along `sign` times the direction `na × nb`, and whether they loop back.
+fn trace(
+    a: &BezierPatch,
+    b: &BezierPatch,
+    seed: Sample,
+    step: f32,
+    tolerance: f32,
+    sign: f32,
+) -> Option<(Vec<Sample>, bool)> {
+    let mut samples = vec![seed];
+    let mut current = seed;
+    for _ in 0..MAX_STEPS {
+        let direction = sign * tangent(a, b, current)?;
+        let target = current.point + step * direction;
+        let next = match correct(a, b, current, target, direction, tolerance) {
+            Some(next) => next,
+            None => break,
+        };
+        if samples.len() > 2 && next.point.distance(seed.point) < step {
+            // Only close when heading back toward the seed, not when just leaving it
+            if (seed.point - current.point).dot(direction) > 0. {
+                return Some((samples, true));
+            }
+        }
+        if let Some(exit) = exit_domain(a, b, current, next, tolerance) {
+            samples.push(exit);
+            break;
+        }
+        samples.push(next);
+        current = next;
+    }
+    Some((samples, false))
+}
+
+/// Unit direction of the intersection curve, or `None` where the patches are tangent.
+fn tangent(a: &BezierPatch, b: &BezierPatch, sample: Sample) -> Option<Vec3> {
+    let (da, db) = (
+        a.derivatives(sample.a.x, sample.a.y),
+        b.derivatives(sample.b.x, sample.b.y),
+    );
+    let (na, nb) = (da.du.cross(da.dv), db.du.cross(db.dv));
+    let direction = na.normalize_or_zero().cross(nb.normalize_or_zero());
+    (direction.length() >= PARALLEL).then(|| direction.normalize())
+}
+
+/// Point common to both patches on the plane through `target` orthogonal to `direction`.
+fn correct(
+    a: &BezierPatch,
+    b: &BezierPatch,
+    from: Sample,
+    target: Vec3,
+    direction: Vec3,
+    tolerance: f32,
+) -> Option<Sample> {
+    let (mut pa, mut pb) = (from.a, from.b);
+    for _ in 0..NEWTON_ITERATIONS {
+        let (da, db) = (a.derivatives(pa.x, pa.y), b.derivatives(pb.x, pb.y));
+        let gap = da.point - db.point;
+        let offset = (da.point - target).dot(direction);
+        if gap.length() < tolerance && offset.abs() < tolerance {
+            return Some(Sample {
+                point: (da.point + db.point) / 2.,
+                a: pa,
+                b: pb,
+            });
+        }
+        let jacobian = Mat4::from_cols(
+            da.du.extend(da.du.dot(direction)),
+            da.dv.extend(da.dv.dot(direction)),
+            (-db.du).extend(0.),
+            (-db.dv).extend(0.),
+        );
+        let inverse = jacobian.inverse();
+        if !inverse.is_finite() {
+            return None;
+        }
+        let delta = inverse * gap.extend(offset);
+        pa -= vec2(delta.x, delta.y);
+        pb -= vec2(delta.z, delta.w);
+        // Let the corrector overshoot the domain a little so that exits can be located
+        pa = pa.clamp(Vec2::splat(-0.5), Vec2::splat(1.5));
+        pb = pb.clamp(Vec2::splat(-0.5), Vec2::splat(1.5));
+    }
+    None
+}
+
+/// Refines parameter guesses on both patches into a common point, moving the least possible in
+/// parameter space.
+fn converge(
+    a: &BezierPatch,
+    b: &BezierPatch,
+    pa: Vec2,
+    pb: Vec2,
+    tolerance: f32,
+) -> Option<Sample> {
+    let (mut pa, mut pb) = (pa, pb);
+    for _ in 0..NEWTON_ITERATIONS {
+        let (da, db) = (a.derivatives(pa.x, pa.y), b.derivatives(pb.x, pb.y));
+        let gap = da.point - db.point;
+        if gap.length() < tolerance {
+            let inside = |p: Vec2| p.cmpge(Vec2::ZERO).all() && p.cmple(Vec2::ONE).all();
+            return (inside(pa) && inside(pb)).then_some(Sample {
+                point: (da.point + db.point) / 2.,
+                a: pa,
+                b: pb,
+            });
+        }
+        // Minimum-norm solution of the underdetermined 3x4 system
+        let columns = jacobian_columns(&da, &db);
+        let normal = columns.iter().fold(Mat3::ZERO, |m, &c| {
+            m + Mat3::from_cols(c * c.x, c * c.y, c * c.z)
+        });
+        if normal.determinant().abs() < f32::EPSILON {
+            return None;
+        }
+        let y = normal.inverse() * gap;
+        let delta = Vec4::from(columns.map(|c| c.dot(y)));
+        pa = (pa - vec2(delta.x, delta.y)).clamp(Vec2::ZERO, Vec2::ONE);
+        pb = (pb - vec2(delta.z, delta.w)).clamp(Vec2::ZERO, Vec2::ONE);
+    }
+    None
+}
+
+fn jacobian_columns(da: &SurfaceDerivatives, db: &SurfaceDerivatives) -> [Vec3; 4] {
+    [da.du, da.dv, -db.du, -db.dv]
+}
+
+/// Sample where the segment from `inside` to `next` leaves either parameter domain, if it does,
+/// moved onto the boundary it crosses.
+fn exit_domain(
+    a: &BezierPatch,
+    b: &BezierPatch,
+    inside: Sample,
+    next: Sample,
+    tolerance: f32,
+) -> Option<Sample> {
+    let (from, to) = (parameters(&inside), parameters(&next));
+    // First parameter to cross a bound, as its index in `parameters` and the bound
+    let (s, index, bound) = (0..4)
+        .filter_map(|k| {
+            let bound = if to[k] < 0. {
+                0.
+            } else if to[k] > 1. {
+                1.
+            } else {
+                return None;
+            };
+            Some(((bound - from[k]) / (to[k] - from[k]), k, bound))
+        })
+        .min_by(|x, y| x.0.total_cmp(&y.0))?;
+    let guess = Sample {
+        point: inside.point.lerp(next.point, s),
+        a: inside.a.lerp(next.a, s).clamp(Vec2::ZERO, Vec2::ONE),
+        b: inside.b.lerp(next.b, s).clamp(Vec2::ZERO, Vec2::ONE),
+    };
+    Some(on_boundary(a, b, guess, index, bound, tolerance).unwrap_or(guess))
+}
+
+/// Point common to both patches with parameter `index` (see [`parameters`]) fixed to `bound`,
+/// by Newton iteration on the other three parameters.
+fn on_boundary(
+    a: &BezierPatch,
+    b: &BezierPatch,
+    guess: Sample,
+    index: usize,
+    bound: f32,
+    tolerance: f32,
+) -> Option<Sample> {
+    let mut params = parameters(&guess);
+    params[index] = bound;
+    let free = [0, 1, 2, 3].map(|k| if k < index { k } else { k + 1 });
+    for _ in 0..NEWTON_ITERATIONS {
+        let (pa, pb) = (vec2(params[0], params[1]), vec2(params[2], params[3]));
+        let (da, db) = (a.derivatives(pa.x, pa.y), b.derivatives(pb.x, pb.y));
+        let gap = da.point - db.point;
+        if gap.length() < tolerance {
+            return Some(Sample {
+                point: (da.point + db.point) / 2.,
+                a: pa,
+                b: pb,
+            });
+        }
+        let columns = jacobian_columns(&da, &db);
+        let inverse =
+            Mat3::from_cols(columns[free[0]], columns[free[1]], columns[free[2]]).inverse();
+        if !inverse.is_finite() {
+            return None;
+        }
+        let delta = inverse * gap;
+        for (&k, d) in free.iter().zip(delta.to_array()) {
+            params[k] = (params[k] - d).clamp(0., 1.);
+        }
+    }
+    None
+}
+
+/// Parameters of the sample on both patches, as `[ua, va, ub, vb]`.
+fn parameters(sample: &Sample) -> [f32; 4] {
+    [sample.a.x, sample.a.y, sample.b.x, sample.b.y]
+}
+
+/// Whether both patches lie in the same plane, where they coincide rather than cross.
+fn coplanar(a: &BezierPatch, b: &BezierPatch, tolerance: f32) -> bool {
+    let derivatives = a.derivatives(0.5, 0.5);
+    let normal = match derivatives.du.cross(derivatives.dv).try_normalize() {
+        Some(normal) => normal,
+        None => return false,
+    };
+    a.points()
+        .iter()
+        .chain(b.points())
+        .all(|p| (*p - derivatives.point).dot(normal).abs() < tolerance)
+}
+
+fn cell(point: Vec3, size: f32) -> IVec3 {
+    (point / size).floor().as_ivec3()
+}
+
+/// The four sub-patches of `patch` split at the middle of both directions, with their ranges.
+fn quarters(patch: &BezierPatch, range: [Vec2; 2]) -> Vec<(BezierPatch, [Vec2; 2])> {
+    let mid = center(range);
+    let (left, right) = patch.split_u(0.5);
+    let mut quarters = Vec::with_capacity(4);
+    for (half, u) in [(left, (range[0].x, mid.x)), (right, (mid.x, range[1].x))] {
+        let (bottom, top) = half.split_v(0.5);
+        quarters.push((bottom, [vec2(u.0, range[0].y), vec2(u.1, mid.y)]));
+        quarters.push((top, [vec2(u.0, mid.y), vec2(u.1, range[1].y)]));
+    }
+    quarters
+}
+
+fn center([start, end]: [Vec2; 2]) -> Vec2 {
+    (start + end) / 2.
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Vec3};
+    use test_log::test;
+
+    use super::IntersectionCurve;
+    use crate::bezier::patch::BezierPatch;
+
+    fn bump() -> BezierPatch {
+        BezierPatch::from_rows([
+            [vec3(0., 0., 0.), vec3(1., 0., 0.), vec3(2., 0., 0.)],
+            [vec3(0., 0., 1.), vec3(1., 2., 1.), vec3(2., 0., 1.)],
+            [vec3(0., 0., 2.), vec3(1., 0., 2.), vec3(2., 0., 2.)],
+        ])
+        .unwrap()
+    }
+
+    fn check(curve: &IntersectionCurve, a: &BezierPatch, b: &BezierPatch) {
+        for ((&point, pa), pb) in curve
+            .points
+            .iter()
+            .zip(&curve.params_a)
+            .zip(&curve.params_b)
+        {
+            assert!((a.get_point(pa.x, pa.y) - point).length() < 1e-3);
+            assert!((b.get_point(pb.x, pb.y) - point).length() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn traces_loops_and_open_curves() {
+        let bump = bump();
+        // y = 8 u (1 - u) v (1 - v) peaks at 0.5, so the plane y = 0.25 cuts a loop around the top
+        let plane = BezierPatch::from_rows([
+            [vec3(-1., 0.25, -1.), vec3(3., 0.25, -1.)],
+            [vec3(-1., 0.25, 3.), vec3(3., 0.25, 3.)],
+        ])
+        .unwrap();
+        let curves = bump.intersect(&plane, 0.02);
+        assert_eq!(curves.len(), 1);
+        let curve = &curves[0];
+        assert!(curve.closed);
+        assert!(curve.points.len() > 20);
+        assert!(curve.points.iter().all(|p| (p.y - 0.25).abs() < 1e-3));
+        check(curve, &bump, &plane);
+
+        // The plane x = 1 crosses the whole bump, from the v = 0 edge to the v = 1 edge
+        let wall = BezierPatch::from_rows([
+            [vec3(1., -1., -1.), vec3(1., -1., 3.)],
+            [vec3(1., 1., -1.), vec3(1., 1., 3.)],
+        ])
+        .unwrap();
+        let curves = bump.intersect(&wall, 0.02);
+        assert_eq!(curves.len(), 1);
+        let curve = &curves[0];
+        assert!(!curve.closed);
+        check(curve, &bump, &wall);
+        let (first, last) = (curve.points[0], *curve.points.last().unwrap());
+        let mut ends = [first.z, last.z];
+        ends.sort_by(|a, b| a.total_cmp(b));
+        assert!(ends[0].abs() < 1e-5 && (ends[1] - 2.).abs() < 1e-5);
+        assert!(curve.points.iter().all(|p| (p.x - 1.).abs() < 1e-3));
+        // End points are on both patches and on the boundary, not just near them
+        for k in [0, curve.points.len() - 1] {
+            let (point, pa, pb) = (curve.points[k], curve.params_a[k], curve.params_b[k]);
+            assert!(pa.y == 0. || pa.y == 1.);
+            assert!((bump.get_point(pa.x, pa.y) - point).length() < 1e-5);
+            assert!((wall.get_point(pb.x, pb.y) - point).length() < 1e-5);
+        }
+
+        let far = BezierPatch::from_rows([
+            [Vec3::Y * 5., vec3(1., 5., 0.)],
+            [vec3(0., 5., 1.), Vec3::ONE * 5.],
+        ])
+        .unwrap();
+        assert!(bump.intersect(&far, 0.02).is_empty());
+    }
+
+    #[test]
+    fn skips_coincident_patches() {
+        let bump = bump();
+        assert!(bump.intersect(&bump, 0.01).is_empty());
+
+        let plane = |size: f32| {
+            BezierPatch::from_rows([
+                [vec3(-size, 0., -size), vec3(size, 0., -size)],
+                [vec3(-size, 0., size), vec3(size, 0., size)],
+            ])
+            .unwrap()
+        };
+        assert!(plane(1.).intersect(&plane(2.), 0.01).is_empty());
+        // Crossings are still found next to a region where the patches coincide
+        let curves = bump.intersect(&plane(3.), 0.02);
+        assert!(!curves.is_empty());
+        for curve in &curves {
+            assert!(curve.points.iter().all(|p| p.y.abs() < 1e-3));
+        }
+    }
+}
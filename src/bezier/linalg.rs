@@ -0,0 +1,45 @@
+use glam::DVec3;
+
+/// Solves the dense `n` × `n` system `A X = B` in place by Gaussian elimination with partial
+/// pivoting, where `A` is given row-major and each unknown is a 3D vector. The solution is left
+/// in `rhs`.
+pub(crate) fn solve(matrix: &mut [f64], rhs: &mut [DVec3]) -> anyhow::Result<()> {
+    let n = rhs.len();
+    anyhow::ensure!(matrix.len() == n * n, "Matrix must be {}x{}", n, n);
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&a, &b| {
+                let (a, b) = (matrix[a * n + col].abs(), matrix[b * n + col].abs());
+                a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap();
+        anyhow::ensure!(
+            matrix[pivot * n + col].abs() > 1e-12,
+            "Singular linear system"
+        );
+        if pivot != col {
+            for k in 0..n {
+                matrix.swap(pivot * n + k, col * n + k);
+            }
+            rhs.swap(pivot, col);
+        }
+        for row in col + 1..n {
+            let factor = matrix[row * n + col] / matrix[col * n + col];
+            if factor == 0. {
+                continue;
+            }
+            for k in col..n {
+                matrix[row * n + k] -= factor * matrix[col * n + k];
+            }
+            rhs[row] -= factor * rhs[col];
+        }
+    }
+    for row in (0..n).rev() {
+        let mut value = rhs[row];
+        for k in row + 1..n {
+            value -= matrix[row * n + k] * rhs[k];
+        }
+        rhs[row] = value / matrix[row * n + row];
+    }
+    Ok(())
+}
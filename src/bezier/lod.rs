@@ -0,0 +1,436 @@
+use glam::Vec3;
+
+use crate::bezier::network::PatchNetwork;
+use crate::bezier::surface::BezierSurface;
+use crate::bezier::tessellation::{Sampling, TessellationBuilder, TessellationJob};
+use crate::camera::Camera;
+use crate::mesh::MeshData;
+use crate::transform::Transform;
+
+/// View-dependent level of detail for a single surface.
+///
+/// The chordal tolerance of the adaptive tessellation is derived from the allowed error in
+/// pixels at the distance of the surface from the camera. Tolerances are quantized to powers of
+/// two, so that the surface is only re-tessellated once the camera moved enough for the projected
+/// error to double or halve.
+///
+/// Tessellation happens either on the calling thread with [`Self::update`], or in the background
+/// with [`Self::update_in_background`].
+#[derive(Debug, Clone)]
+pub struct SurfaceLod {
+    pixel_error: f32,
+    max_angle: f32,
+    max_depth: u32,
+    builder: TessellationBuilder,
+    level: Option<i32>,
+    pending: Option<(i32, TessellationJob)>,
+}
+
+impl SurfaceLod {
+    pub fn new(pixel_error: f32) -> Self {
+        Self {
+            pixel_error,
+            max_angle: 10f32.to_radians(),
+            max_depth: 8,
+            builder: TessellationBuilder::default(),
+            level: None,
+            pending: None,
+        }
+    }
+
+    pub fn with_max_angle(mut self, angle: f32) -> Self {
+        self.max_angle = angle;
+        self
+    }
+
+    pub fn with_max_depth(mut self, depth: u32) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Tessellation settings other than the sampling, which is chosen by the level of detail.
+    pub fn with_builder(mut self, builder: TessellationBuilder) -> Self {
+        self.builder = builder;
+        self
+    }
+
+    /// Current level, as the base 2 logarithm of the chordal tolerance in model space.
+    pub fn level(&self) -> Option<i32> {
+        self.level
+    }
+
+    /// Forces the next update to tessellate the surface again, discarding any tessellation still
+    /// running in the background.
+    pub fn invalidate(&mut self) {
+        self.level = None;
+        self.pending = None;
+    }
+
+    /// World-space chordal tolerance matching the allowed pixel error.
+    pub fn tolerance(
+        &self,
+        surface: &BezierSurface,
+        transform: &Transform,
+        camera: &Camera,
+    ) -> f32 {
+        let bounds = surface.evaluated_patch().bounding_box();
+        tolerance(self.pixel_error, bounds, transform, camera)
+    }
+
+    /// Returns a new tessellation of the surface if the level of detail changed.
+    pub fn update(
+        &mut self,
+        surface: &BezierSurface,
+        transform: &Transform,
+        camera: &Camera,
+    ) -> anyhow::Result<Option<MeshData>> {
+        let level = self.target_level(surface, transform, camera)?;
+        if self.level == Some(level) {
+            return Ok(None);
+        }
+        let data = self.builder(level).build(surface)?;
+        tracing::debug!(message = "Surface re-tessellated", %level, vertices = data.vertices.len());
+        self.level = Some(level);
+        Ok(Some(data))
+    }
+
+    /// Same as [`Self::update`], but tessellates on the rayon thread pool instead of blocking.
+    ///
+    /// Returns the new tessellation on a later call, once it is done. Level changes requested
+    /// while a tessellation is running are picked up after it finishes.
+    pub fn update_in_background(
+        &mut self,
+        surface: &BezierSurface,
+        transform: &Transform,
+        camera: &Camera,
+    ) -> anyhow::Result<Option<MeshData>> {
+        if let Some((level, job)) = &self.pending {
+            let level = *level;
+            return match job.try_take() {
+                Some(result) => {
+                    self.pending = None;
+                    let data = result?;
+                    tracing::debug!(
+                        message = "Surface re-tessellated in background",
+                        %level,
+                        vertices = data.vertices.len()
+                    );
+                    self.level = Some(level);
+                    Ok(Some(data))
+                }
+                None => Ok(None),
+            };
+        }
+        let level = self.target_level(surface, transform, camera)?;
+        if self.level != Some(level) {
+            let job = self.builder(level).spawn(surface.clone());
+            self.pending = Some((level, job));
+        }
+        Ok(None)
+    }
+
+    fn target_level(
+        &self,
+        surface: &BezierSurface,
+        transform: &Transform,
+        camera: &Camera,
+    ) -> anyhow::Result<i32> {
+        level(self.tolerance(surface, transform, camera), transform)
+    }
+
+    fn builder(&self, level: i32) -> TessellationBuilder {
+        adaptive(&self.builder, level, self.max_angle, self.max_depth)
+    }
+}
+
+/// View-dependent level of detail for the patches of a network.
+///
+/// Each patch gets its own level, chosen as for a [`SurfaceLod`] from its own distance to the
+/// camera. Stitched edges are sampled at the finer level of their two sides, so that patches at
+/// different levels meet without cracks. The whole network is re-tessellated once the level of
+/// any patch changed.
+#[derive(Debug, Clone)]
+pub struct NetworkLod {
+    pixel_error: f32,
+    max_angle: f32,
+    max_depth: u32,
+    builder: TessellationBuilder,
+    levels: Option<Vec<i32>>,
+}
+
+impl NetworkLod {
+    pub fn new(pixel_error: f32) -> Self {
+        Self {
+            pixel_error,
+            max_angle: 10f32.to_radians(),
+            max_depth: 8,
+            builder: TessellationBuilder::default(),
+            levels: None,
+        }
+    }
+
+    pub fn with_max_angle(mut self, angle: f32) -> Self {
+        self.max_angle = angle;
+        self
+    }
+
+    pub fn with_max_depth(mut self, depth: u32) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Tessellation settings other than the sampling, which is chosen by the level of detail.
+    pub fn with_builder(mut self, builder: TessellationBuilder) -> Self {
+        self.builder = builder;
+        self
+    }
+
+    /// Current level of each patch, as the base 2 logarithm of its chordal tolerance in model
+    /// space.
+    pub fn levels(&self) -> Option<&[i32]> {
+        self.levels.as_deref()
+    }
+
+    /// Forces the next update to tessellate the network again.
+    pub fn invalidate(&mut self) {
+        self.levels = None;
+    }
+
+    /// Returns a new tessellation of the network if the level of detail of any patch changed.
+    pub fn update(
+        &mut self,
+        network: &PatchNetwork,
+        transform: &Transform,
+        camera: &Camera,
+    ) -> anyhow::Result<Option<MeshData>> {
+        let levels = network
+            .patches()
+            .iter()
+            .map(|patch| {
+                let tolerance =
+                    tolerance(self.pixel_error, patch.bounding_box(), transform, camera);
+                level(tolerance, transform)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if self.levels.as_ref() == Some(&levels) {
+            return Ok(None);
+        }
+        let builders = levels
+            .iter()
+            .map(|&level| adaptive(&self.builder, level, self.max_angle, self.max_depth))
+            .collect::<Vec<_>>();
+        let data = network.tessellate_adaptive(&builders)?;
+        tracing::debug!(
+            message = "Network re-tessellated",
+            ?levels,
+            vertices = data.vertices.len()
+        );
+        self.levels = Some(levels);
+        Ok(Some(data))
+    }
+}
+
+/// World-space chordal tolerance matching `pixel_error` at the distance of the bounding box
+/// `(min, max)` from the camera.
+fn tolerance(
+    pixel_error: f32,
+    (min, max): (Vec3, Vec3),
+    transform: &Transform,
+    camera: &Camera,
+) -> f32 {
+    let center = transform.matrix().transform_point3((min + max) / 2.);
+    let radius = (max - min).length() / 2. * max_scale(transform);
+    let distance =
+        (camera.position().distance(center) - radius).max(camera.projection.zrange.start);
+    pixel_error * camera.projection.pixel_size(distance)
+}
+
+/// Level of a world-space chordal tolerance, from the tolerance in model space.
+fn level(tolerance: f32, transform: &Transform) -> anyhow::Result<i32> {
+    let tolerance = tolerance / max_scale(transform);
+    anyhow::ensure!(
+        tolerance.is_finite() && tolerance > 0.,
+        "Chordal tolerance {} is not positive and finite",
+        tolerance
+    );
+    Ok(tolerance.log2().floor() as i32)
+}
+
+fn max_scale(transform: &Transform) -> f32 {
+    transform.scale.abs().max_element()
+}
+
+fn adaptive(
+    builder: &TessellationBuilder,
+    level: i32,
+    max_angle: f32,
+    max_depth: u32,
+) -> TessellationBuilder {
+    builder.clone().with_sampling(Sampling::Adaptive {
+        tolerance: 2f32.powi(level),
+        max_angle,
+        max_depth,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use glam::{vec3, Vec3};
+    use test_log::test;
+
+    use super::{NetworkLod, SurfaceLod};
+    use crate::bezier::network::{Continuity, Edge, PatchNetwork};
+    use crate::bezier::patch::BezierPatch;
+    use crate::bezier::surface::BezierSurface;
+    use crate::camera::{Camera, Projection};
+    use crate::transform::Transform;
+
+    #[test]
+    fn retessellates_on_camera_distance() {
+        let surface = BezierSurface::from(
+            BezierPatch::from_rows([
+                [vec3(-1., 0., -1.), vec3(0., 1., -1.), vec3(1., 0., -1.)],
+                [vec3(-1., 1., 0.), vec3(0., -1., 0.), vec3(1., 1., 0.)],
+                [vec3(-1., 0., 1.), vec3(0., 1., 1.), vec3(1., 0., 1.)],
+            ])
+            .unwrap(),
+        );
+        let camera_at = |z: f32| Camera {
+            transform: Transform::translation(vec3(0., 0., z)).looking_at(Vec3::ZERO),
+            projection: Projection {
+                width: 800.,
+                height: 600.,
+                ..Default::default()
+            },
+        };
+        let transform = Transform::default();
+        let mut lod = SurfaceLod::new(1.);
+
+        let near = lod.update(&surface, &transform, &camera_at(3.)).unwrap();
+        let near = near.expect("First update tessellates");
+        assert!(lod
+            .update(&surface, &transform, &camera_at(3.01))
+            .unwrap()
+            .is_none());
+        let far = lod.update(&surface, &transform, &camera_at(50.)).unwrap();
+        let far = far.expect("Moving away changes the level of detail");
+        assert!(far.vertices.len() < near.vertices.len());
+
+        // A scaled-up surface is closer and needs a finer tolerance in model space
+        let level = lod.level().unwrap();
+        let scaled = Transform {
+            scale: Vec3::splat(4.),
+            ..Default::default()
+        };
+        lod.update(&surface, &scaled, &camera_at(50.)).unwrap();
+        assert!(lod.level().unwrap() <= level - 2);
+        assert!(SurfaceLod::new(0.)
+            .update(&surface, &transform, &camera_at(3.))
+            .is_err());
+    }
+
+    #[test]
+    fn tessellates_in_background() {
+        let surface = BezierSurface::from(
+            BezierPatch::from_rows([
+                [vec3(-1., 0., -1.), vec3(1., 0., -1.)],
+                [vec3(-1., 1., 1.), vec3(1., -1., 1.)],
+            ])
+            .unwrap(),
+        );
+        let camera = Camera {
+            transform: Transform::translation(vec3(0., 0., 5.)).looking_at(Vec3::ZERO),
+            projection: Projection {
+                width: 800.,
+                height: 600.,
+                ..Default::default()
+            },
+        };
+        let transform = Transform::default();
+        let expected = SurfaceLod::new(1.)
+            .update(&surface, &transform, &camera)
+            .unwrap()
+            .unwrap();
+
+        let mut lod = SurfaceLod::new(1.);
+        let start = Instant::now();
+        let data = loop {
+            if let Some(data) = lod
+                .update_in_background(&surface, &transform, &camera)
+                .unwrap()
+            {
+                break data;
+            }
+            assert!(start.elapsed() < Duration::from_secs(10));
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(data.vertices.len(), expected.vertices.len());
+        assert_eq!(data.indices, expected.indices);
+        assert!(lod
+            .update_in_background(&surface, &transform, &camera)
+            .unwrap()
+            .is_none());
+        assert!(lod.pending.is_none());
+    }
+
+    #[test]
+    fn network_patches_meet_at_their_own_levels() {
+        // Two patches in a row, bulging along the edge they share
+        let strip = |x: f32| {
+            BezierPatch::from_rows((0..3).map(|j| {
+                let z = if j == 1 { 1. } else { 0. };
+                (0..3).map(move |i| vec3(x + 2. * i as f32, j as f32 / 2., z))
+            }))
+            .unwrap()
+        };
+        let mut network = PatchNetwork::new();
+        let a = network.add_patch(strip(0.));
+        let b = network.add_patch(strip(4.));
+        network
+            .connect((a, Edge::Right), (b, Edge::Left), Continuity::G0)
+            .unwrap();
+        let camera = Camera {
+            transform: Transform::translation(vec3(-2., 0.5, 1.)).looking_at(vec3(0., 0.5, 0.)),
+            projection: Projection {
+                width: 800.,
+                height: 600.,
+                ..Default::default()
+            },
+        };
+        let transform = Transform::default();
+        let mut lod = NetworkLod::new(0.5);
+        let data = lod.update(&network, &transform, &camera).unwrap().unwrap();
+        let levels = lod.levels().unwrap();
+        assert!(levels[a] < levels[b]);
+        assert!(lod.update(&network, &transform, &camera).unwrap().is_none());
+
+        // Edges used by a single triangle all lie on the outside of the strip
+        let mut edges = std::collections::HashMap::new();
+        for triangle in data.indices.chunks(3) {
+            for k in 0..3 {
+                let (i, j) = (triangle[k], triangle[(k + 1) % 3]);
+                *edges.entry((i.min(j), i.max(j))).or_insert(0) += 1;
+            }
+        }
+        let mut seam = 0;
+        for (&(i, j), &count) in &edges {
+            let (pi, pj) = (
+                data.vertices[i as usize].position,
+                data.vertices[j as usize].position,
+            );
+            let middle = (pi + pj) / 2.;
+            if (middle.x - 4.).abs() < 1e-4 {
+                seam += 1;
+            }
+            if count == 1 {
+                let outside = [middle.x, 8. - middle.x, middle.y, 1. - middle.y]
+                    .iter()
+                    .any(|d| d.abs() < 1e-4);
+                assert!(outside, "Crack at {}", middle);
+            }
+        }
+        assert!(seam > 2);
+    }
+}
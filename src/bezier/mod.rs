@@ -1,2 +1,22 @@
+pub mod basis;
+pub mod construction;
+pub mod coons;
+pub mod curvature;
 pub mod curve;
+pub mod displacement;
+pub mod fitting;
+pub(crate) mod integration;
+pub mod intersection;
+pub(crate) mod linalg;
+pub mod lod;
+pub mod network;
+pub mod nurbs;
+pub mod parametric;
+pub mod patch;
+pub mod query;
+pub mod solid;
 pub mod surface;
+pub mod surface_curve;
+pub mod tessellation;
+pub mod triangle;
+pub mod trim;
@@ -0,0 +1,655 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use glam::Vec3;
+use rayon::prelude::*;
+
+use crate::bezier::patch::BezierPatch;
+use crate::bezier::surface::BezierSurface;
+use crate::bezier::tessellation::TessellationBuilder;
+use crate::mesh::MeshData;
+
+/// Boundary of a patch, named after its position in the parameter domain.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Edge {
+    /// `v = 0`, along `u`.
+    Bottom,
+    /// `v = 1`, along `u`.
+    Top,
+    /// `u = 0`, along `v`.
+    Left,
+    /// `u = 1`, along `v`.
+    Right,
+}
+
+impl Edge {
+    pub(crate) const ALL: [Self; 4] = [Self::Bottom, Self::Top, Self::Left, Self::Right];
+
+    /// Grid coordinates of the control points `depth` rows away from the edge, in the order of
+    /// the edge parameter.
+    fn indices(self, patch: &BezierPatch, depth: usize) -> Vec<(usize, usize)> {
+        let (w, h) = (patch.width(), patch.height());
+        match self {
+            Self::Bottom => (0..w).map(|i| (i, depth)).collect(),
+            Self::Top => (0..w).map(|i| (i, h - 1 - depth)).collect(),
+            Self::Left => (0..h).map(|j| (depth, j)).collect(),
+            Self::Right => (0..h).map(|j| (w - 1 - depth, j)).collect(),
+        }
+    }
+
+    fn points(self, patch: &BezierPatch, depth: usize) -> Vec<Vec3> {
+        self.indices(patch, depth)
+            .into_iter()
+            .map(|(i, j)| patch.get(i, j))
+            .collect()
+    }
+
+    /// Number of control rows across the edge.
+    fn depth(self, patch: &BezierPatch) -> usize {
+        match self {
+            Self::Bottom | Self::Top => patch.height(),
+            Self::Left | Self::Right => patch.width(),
+        }
+    }
+
+    /// Whether the edge runs along the counter-clockwise boundary of the domain.
+    fn is_forward(self) -> bool {
+        matches!(self, Self::Bottom | Self::Right)
+    }
+
+    /// Grid coordinates of tessellation vertex `k` along the edge, in a `n` × `n` grid.
+    fn sample(self, n: usize, k: usize) -> usize {
+        match self {
+            Self::Bottom => k,
+            Self::Top => (n - 1) * n + k,
+            Self::Left => k * n,
+            Self::Right => k * n + n - 1,
+        }
+    }
+}
+
+/// Geometric continuity maintained across a shared edge.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Continuity {
+    /// Positional continuity: the edges coincide.
+    G0,
+    /// Tangent plane continuity: the control points across the edge are collinear, with a
+    /// constant ratio along the edge.
+    G1,
+}
+
+/// Edge shared between two patches of a [`PatchNetwork`].
+///
+/// Patch `b` follows patch `a`: enforcing the link moves control points of `b` only.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Link {
+    pub a: (usize, Edge),
+    pub b: (usize, Edge),
+    /// Whether the edges run in opposite directions.
+    pub reversed: bool,
+    pub continuity: Continuity,
+}
+
+/// Collection of Bézier patches stitched together along their edges.
+#[derive(Debug, Clone, Default)]
+pub struct PatchNetwork {
+    patches: Vec<BezierPatch>,
+    links: Vec<Link>,
+}
+
+impl PatchNetwork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a patch to the network, returning its index.
+    pub fn add_patch(&mut self, patch: BezierPatch) -> usize {
+        self.patches.push(patch);
+        self.patches.len() - 1
+    }
+
+    pub fn patches(&self) -> &[BezierPatch] {
+        &self.patches
+    }
+
+    pub fn patch(&self, id: usize) -> &BezierPatch {
+        &self.patches[id]
+    }
+
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /// Patches adjacent to patch `id`, as the edge of `id`, the other patch and its edge.
+    pub fn neighbors(&self, id: usize) -> impl Iterator<Item = (Edge, usize, Edge)> + '_ {
+        self.links
+            .iter()
+            .filter_map(move |link| match (link.a, link.b) {
+                ((a, ea), (b, eb)) if a == id => Some((ea, b, eb)),
+                ((a, ea), (b, eb)) if b == id => Some((eb, a, ea)),
+                _ => None,
+            })
+    }
+
+    /// Stitches edge `edge_b` of patch `b` to edge `edge_a` of patch `a`, and enforces the
+    /// continuity by moving control points of `b`.
+    ///
+    /// Both edges must have the same number of control points and coincide, and the patches
+    /// must be oriented consistently, so that their normals agree across the edge.
+    ///
+    /// Links cannot form cycles. Several edges of a patch may follow other edges, see
+    /// [`Self::enforce`] for how their constraints are combined.
+    pub fn connect(
+        &mut self,
+        (a, edge_a): (usize, Edge),
+        (b, edge_b): (usize, Edge),
+        continuity: Continuity,
+    ) -> anyhow::Result<()> {
+        const EPSILON: f32 = 1e-4;
+        anyhow::ensure!(
+            a < self.patches.len() && b < self.patches.len(),
+            "Patch index out of bounds"
+        );
+        anyhow::ensure!(a != b, "Cannot connect a patch to itself");
+        anyhow::ensure!(
+            !self.links.iter().any(|link| link.b == (b, edge_b)),
+            "Edge {:?} of patch {} already follows another edge",
+            edge_b,
+            b
+        );
+        let (pa, pb) = (&self.patches[a], &self.patches[b]);
+        let (points_a, points_b) = (edge_a.points(pa, 0), edge_b.points(pb, 0));
+        anyhow::ensure!(
+            points_a.len() == points_b.len(),
+            "Edges have {} and {} control points, elevate the patches to a common degree first",
+            points_a.len(),
+            points_b.len()
+        );
+        let coincide = |reversed: bool| {
+            points_a
+                .iter()
+                .zip(reversed_if(&points_b, reversed))
+                .all(|(p, q)| p.distance(*q) <= EPSILON)
+        };
+        let reversed = if coincide(false) {
+            false
+        } else if coincide(true) {
+            true
+        } else {
+            anyhow::bail!("Edges {:?} and {:?} do not coincide", edge_a, edge_b);
+        };
+        anyhow::ensure!(
+            (edge_a.is_forward() == edge_b.is_forward()) == reversed,
+            "Patches {} and {} have opposite orientations",
+            a,
+            b
+        );
+        if continuity == Continuity::G1 {
+            anyhow::ensure!(
+                edge_a.depth(pa) >= 2 && edge_b.depth(pb) >= 2,
+                "G1 continuity needs patches of degree at least 1 across the edge"
+            );
+        }
+
+        anyhow::ensure!(
+            !self.leads(b, a),
+            "Patch {} already follows patch {}, links cannot form a cycle",
+            a,
+            b
+        );
+
+        self.links.push(Link {
+            a: (a, edge_a),
+            b: (b, edge_b),
+            reversed,
+            continuity,
+        });
+        self.enforce();
+        Ok(())
+    }
+
+    /// Edits patch `id`, then restores the continuity constraints of the network.
+    ///
+    /// Control points of follower edges are overwritten by the edge they follow. The size of the
+    /// control grid must not change.
+    pub fn edit<T>(&mut self, id: usize, f: impl FnOnce(&mut BezierPatch) -> T) -> T {
+        let ret = f(&mut self.patches[id]);
+        self.enforce();
+        ret
+    }
+
+    pub fn set_point(&mut self, id: usize, i: usize, j: usize, point: Vec3) {
+        self.edit(id, |patch| *patch.get_mut(i, j) = point);
+    }
+
+    /// Enforces the continuity of all links, leaders before their followers.
+    ///
+    /// Edges follow their leader exactly. The inner control points moved for G1 continuity give
+    /// way to the edges of other links into the same patch, and points moved by several links,
+    /// such as the twist next to the corner of two G1 edges, go to the average of their targets.
+    pub fn enforce(&mut self) {
+        // Number of links between each patch and the patches it follows, the longest way
+        let mut rank = vec![0; self.patches.len()];
+        for _ in 0..self.patches.len() {
+            for link in &self.links {
+                rank[link.b.0] = rank[link.b.0].max(rank[link.a.0] + 1);
+            }
+        }
+        let mut followers = self.links.iter().map(|link| link.b.0).collect::<Vec<_>>();
+        followers.sort_by_key(|&id| (rank[id], id));
+        followers.dedup();
+        for id in followers {
+            self.enforce_patch(id);
+        }
+    }
+
+    /// Whether patch `b` follows patch `a`, directly or through other patches.
+    fn leads(&self, a: usize, b: usize) -> bool {
+        let mut stack = vec![b];
+        let mut visited = vec![false; self.patches.len()];
+        while let Some(id) = stack.pop() {
+            if id == a {
+                return true;
+            }
+            if !std::mem::replace(&mut visited[id], true) {
+                stack.extend(self.links.iter().filter(|l| l.b.0 == id).map(|l| l.a.0));
+            }
+        }
+        false
+    }
+
+    /// Moves the control points of patch `id` constrained by the links it follows.
+    fn enforce_patch(&mut self, id: usize) {
+        let mut edges = HashMap::<_, (Vec3, f32)>::new();
+        let mut inner = HashMap::<_, (Vec3, f32)>::new();
+        for link in self.links.iter().filter(|link| link.b.0 == id) {
+            for (depth, index, point) in self.targets(link) {
+                let targets = if depth == 0 { &mut edges } else { &mut inner };
+                let (sum, count) = targets.entry(index).or_default();
+                *sum += point;
+                *count += 1.;
+            }
+        }
+        inner.retain(|index, _| !edges.contains_key(index));
+        let patch = &mut self.patches[id];
+        for (&(i, j), &(sum, count)) in edges.iter().chain(&inner) {
+            *patch.get_mut(i, j) = sum / count;
+        }
+    }
+
+    /// Positions required by the link for the control points of patch `b`, as their depth from
+    /// the edge, their grid coordinates and their position.
+    fn targets(&self, link: &Link) -> Vec<(usize, (usize, usize), Vec3)> {
+        let ((a, edge_a), (b, edge_b)) = (link.a, link.b);
+        let (leader, patch) = (&self.patches[a], &self.patches[b]);
+        let edge = edge_a.points(leader, 0);
+        let edge = reversed_if(&edge, link.reversed)
+            .copied()
+            .collect::<Vec<_>>();
+        let mut targets = edge_b
+            .indices(patch, 0)
+            .into_iter()
+            .zip(edge.iter().copied())
+            .map(|(index, point)| (0, index, point))
+            .collect::<Vec<_>>();
+        if link.continuity == Continuity::G0 {
+            return targets;
+        }
+
+        let inner_a = edge_a.points(leader, 1);
+        let inner_a = reversed_if(&inner_a, link.reversed);
+        let indices = edge_b.indices(patch, 1);
+        let directions = edge
+            .iter()
+            .zip(inner_a)
+            .map(|(e, p)| *e - *p)
+            .collect::<Vec<_>>();
+        let length_a = directions.iter().map(|d| d.length()).sum::<f32>();
+        let length_b = indices
+            .iter()
+            .zip(&edge)
+            .map(|(&(i, j), e)| patch.get(i, j).distance(*e))
+            .sum::<f32>();
+        if length_a <= f32::EPSILON {
+            return targets;
+        }
+        // An inner row collapsed onto the edge has no length to keep, mirror the other patch
+        let ratio = if length_b <= f32::EPSILON {
+            1.
+        } else {
+            length_b / length_a
+        };
+        targets.extend(
+            indices
+                .into_iter()
+                .zip(edge.iter().zip(directions))
+                .map(|(index, (e, d))| (1, index, *e + ratio * d)),
+        );
+        targets
+    }
+
+    /// Tessellates every patch on a `resolution` × `resolution` grid with the settings of
+    /// `builder`, into a single mesh whose vertices are shared along stitched edges.
+    ///
+    /// Normals of shared vertices are averaged between the patches.
+    pub fn tessellate(
+        &self,
+        builder: &TessellationBuilder,
+        resolution: usize,
+    ) -> anyhow::Result<MeshData> {
+        let builder = builder.clone().with_resolution(resolution, resolution);
+        let meshes = self
+            .patches
+            .par_iter()
+            .enumerate()
+            .map(|(id, patch)| {
+                builder
+                    .build(&BezierSurface::from(patch.clone()))
+                    .with_context(|| format!("Cannot tessellate patch {}", id))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let shared = self.links.iter().flat_map(|link| {
+            let ((a, edge_a), (b, edge_b)) = (link.a, link.b);
+            (0..resolution).map(move |k| {
+                let kb = if link.reversed { resolution - 1 - k } else { k };
+                (
+                    (a, edge_a.sample(resolution, k)),
+                    (b, edge_b.sample(resolution, kb)),
+                )
+            })
+        });
+        Ok(stitch(meshes, shared))
+    }
+
+    /// Tessellates patch `id` with `builders[id]`, into a single mesh whose vertices are shared
+    /// along stitched edges. The builders must sample adaptively, with the same maximum depth.
+    ///
+    /// Each side of a stitched edge gets the vertices of the other side, so that patches refined
+    /// to different levels meet without cracks. Normals of shared vertices are averaged between
+    /// the patches.
+    pub fn tessellate_adaptive(
+        &self,
+        builders: &[TessellationBuilder],
+    ) -> anyhow::Result<MeshData> {
+        anyhow::ensure!(
+            builders.len() == self.patches.len(),
+            "Expected {} tessellation builders, got {}",
+            self.patches.len(),
+            builders.len()
+        );
+        let surfaces = self
+            .patches
+            .iter()
+            .cloned()
+            .map(BezierSurface::from)
+            .collect::<Vec<_>>();
+        let mut grids = surfaces
+            .par_iter()
+            .zip(builders)
+            .enumerate()
+            .map(|(id, (surface, builder))| {
+                builder
+                    .adaptive_grid(surface)
+                    .with_context(|| format!("Cannot tessellate patch {}", id))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        anyhow::ensure!(
+            grids
+                .windows(2)
+                .all(|pair| pair[0].size() == pair[1].size()),
+            "Tessellation builders have different maximum depths"
+        );
+
+        // Vertices added along an edge of a closed patch also go to the opposite edge, which
+        // may be stitched to another patch: exchange until no edge changes
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for link in &self.links {
+                let ((a, edge_a), (b, edge_b)) = (link.a, link.b);
+                let n = grids[a].size();
+                let other = |k: u32| if link.reversed { n - k } else { k };
+                let along_a = grids[a]
+                    .edge(edge_a)
+                    .into_iter()
+                    .map(other)
+                    .collect::<Vec<_>>();
+                let along_b = grids[b]
+                    .edge(edge_b)
+                    .into_iter()
+                    .map(other)
+                    .collect::<Vec<_>>();
+                changed |= grids[b].insert_edge(edge_b, along_a);
+                changed |= grids[a].insert_edge(edge_a, along_b);
+            }
+        }
+
+        let (meshes, edges): (Vec<_>, Vec<_>) = surfaces
+            .par_iter()
+            .zip(builders)
+            .zip(&grids)
+            .map(|((surface, builder), grid)| builder.build_grid(surface, grid))
+            .unzip();
+        let along = |id: usize, edge: Edge| {
+            let index = Edge::ALL.iter().position(|&e| e == edge).unwrap();
+            &edges[id][index]
+        };
+        let shared = self.links.iter().flat_map(|link| {
+            let ((a, edge_a), (b, edge_b)) = (link.a, link.b);
+            along(a, edge_a)
+                .iter()
+                .zip(reversed_if(along(b, edge_b), link.reversed))
+                .map(move |(&va, &vb)| ((a, va), (b, vb)))
+        });
+        Ok(stitch(meshes, shared))
+    }
+}
+
+/// Concatenates the meshes of the patches, merging the pairs of vertices given as the patch and
+/// the index of the vertex in its mesh.
+fn stitch(
+    meshes: Vec<MeshData>,
+    shared: impl IntoIterator<Item = ((usize, usize), (usize, usize))>,
+) -> MeshData {
+    let mut data = MeshData::default();
+    let mut offsets = Vec::with_capacity(meshes.len());
+    for mesh in meshes {
+        let offset = data.vertices.len();
+        offsets.push(offset);
+        data.vertices.extend(mesh.vertices);
+        data.indices
+            .extend(mesh.indices.into_iter().map(|i| i + offset as u32));
+    }
+
+    // Union-find over the vertices, merging samples of stitched edges
+    let mut parent = (0..data.vertices.len()).collect::<Vec<_>>();
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for ((a, va), (b, vb)) in shared {
+        let va = find(&mut parent, offsets[a] + va);
+        let vb = find(&mut parent, offsets[b] + vb);
+        parent[va.max(vb)] = va.min(vb);
+    }
+
+    let mut remap = vec![0; data.vertices.len()];
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    for v in 0..data.vertices.len() {
+        let root = find(&mut parent, v);
+        if root == v {
+            remap[v] = vertices.len();
+            vertices.push(data.vertices[v]);
+            normals.push(Vec3::ZERO);
+        } else {
+            remap[v] = remap[root];
+        }
+        normals[remap[v]] += data.vertices[v].normal;
+    }
+    for (vertex, normal) in vertices.iter_mut().zip(normals) {
+        vertex.normal = normal.normalize_or_zero();
+        vertex.tangent = vertex
+            .tangent
+            .reject_from(vertex.normal)
+            .normalize_or_zero();
+    }
+
+    MeshData {
+        vertices,
+        indices: data
+            .indices
+            .into_iter()
+            .map(|i| remap[i as usize] as u32)
+            .collect(),
+    }
+}
+
+fn reversed_if<T>(items: &[T], reversed: bool) -> Box<dyn Iterator<Item = &T> + '_> {
+    if reversed {
+        Box::new(items.iter().rev())
+    } else {
+        Box::new(items.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Vec3};
+    use test_log::test;
+
+    use super::{Continuity, Edge, PatchNetwork};
+    use crate::bezier::patch::BezierPatch;
+    use crate::bezier::tessellation::TessellationBuilder;
+
+    fn grid(x: f32, y: f32) -> BezierPatch {
+        BezierPatch::from_rows(
+            (0..3).map(|j| (0..3).map(move |i| vec3(x + i as f32 / 2., y + j as f32 / 2., 0.))),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn stitched_patches_stay_continuous() {
+        let mut network = PatchNetwork::new();
+        let a = network.add_patch(grid(0., 0.));
+        let b = network.add_patch(grid(1., 0.));
+        network
+            .connect((a, Edge::Right), (b, Edge::Left), Continuity::G1)
+            .unwrap();
+        assert!(network
+            .connect((a, Edge::Bottom), (b, Edge::Top), Continuity::G0)
+            .is_err());
+        assert_eq!(
+            network.neighbors(b).collect::<Vec<_>>(),
+            [(Edge::Left, a, Edge::Right)]
+        );
+
+        network.set_point(a, 1, 1, vec3(0.5, 0.5, -1.));
+        network.set_point(a, 2, 1, vec3(1., 0.5, 0.));
+        let (edge, inner_a, inner_b) = (
+            network.patch(b).get(0, 1),
+            network.patch(a).get(1, 1),
+            network.patch(b).get(1, 1),
+        );
+        assert!((edge - vec3(1., 0.5, 0.)).length() < 1e-6);
+        let (da, db) = (edge - inner_a, inner_b - edge);
+        assert!(da.cross(db).length() < 1e-5 && da.dot(db) > 0.);
+
+        let n = 5;
+        let data = network
+            .tessellate(&TessellationBuilder::default(), n)
+            .unwrap();
+        assert_eq!(data.vertices.len(), 2 * n * n - n);
+        assert_eq!(data.indices.len(), 2 * (n - 1) * (n - 1) * 6);
+        let shared = data.vertices[n - 1];
+        assert!((shared.normal - Vec3::Z).length() < 1e-5);
+    }
+
+    #[test]
+    fn links_are_ordered_and_checked() {
+        let mut network = PatchNetwork::new();
+        let [a, b, c] = [0., 1., 2.].map(|x| network.add_patch(grid(x, 0.)));
+        // Links added before the link of their leader still follow it
+        network
+            .connect((b, Edge::Right), (c, Edge::Left), Continuity::G1)
+            .unwrap();
+        network
+            .connect((a, Edge::Right), (b, Edge::Left), Continuity::G1)
+            .unwrap();
+        network.set_point(a, 1, 1, vec3(0.5, 0.5, -1.));
+        let (inner_b, edge, inner_c) = (
+            network.patch(b).get(1, 1),
+            network.patch(b).get(2, 1),
+            network.patch(c).get(1, 1),
+        );
+        assert!(inner_b.z > 0.);
+        assert!((edge - inner_b).cross(inner_c - edge).length() < 1e-5);
+
+        assert!(network
+            .connect((b, Edge::Left), (a, Edge::Right), Continuity::G0)
+            .is_err());
+
+        // A collapsed inner row mirrors the leader across the edge
+        let mut network = PatchNetwork::new();
+        let a = network.add_patch(grid(0., 0.));
+        let mut collapsed = grid(1., 0.);
+        for j in 0..3 {
+            *collapsed.get_mut(1, j) = collapsed.get(0, j);
+        }
+        let b = network.add_patch(collapsed);
+        network
+            .connect((a, Edge::Right), (b, Edge::Left), Continuity::G1)
+            .unwrap();
+        assert!((network.patch(b).get(1, 1) - vec3(1.5, 0.5, 0.)).length() < 1e-6);
+
+        // The edge of another link takes over the inner row of a G1 link
+        let below = network.add_patch(grid(1., -1.));
+        network
+            .connect((below, Edge::Top), (b, Edge::Bottom), Continuity::G0)
+            .unwrap();
+        network.set_point(a, 1, 0, vec3(0.5, 0., -1.));
+        assert_eq!(network.patch(b).get(1, 0), network.patch(below).get(1, 2));
+    }
+
+    #[test]
+    fn grids_of_patches_stay_smooth() {
+        let cubic = |x: f32, y: f32| {
+            BezierPatch::from_rows(
+                (0..4).map(|j| (0..4).map(move |i| vec3(x + i as f32 / 3., y + j as f32 / 3., 0.))),
+            )
+            .unwrap()
+        };
+        let mut network = PatchNetwork::new();
+        let [a, b, c, d] =
+            [(0., 0.), (1., 0.), (0., 1.), (1., 1.)].map(|(x, y)| network.add_patch(cubic(x, y)));
+        for (leader, follower, edge) in [
+            (a, b, (Edge::Right, Edge::Left)),
+            (a, c, (Edge::Top, Edge::Bottom)),
+            (b, d, (Edge::Top, Edge::Bottom)),
+            (c, d, (Edge::Right, Edge::Left)),
+        ] {
+            network
+                .connect((leader, edge.0), (follower, edge.1), Continuity::G1)
+                .unwrap();
+        }
+        network.set_point(a, 2, 2, vec3(0.7, 0.6, 0.5));
+        network.set_point(a, 1, 3, vec3(0.3, 1., 0.2));
+
+        // Edges coincide, and the control points across them stay collinear
+        for link in network.links() {
+            let ((pa, ea), (pb, eb)) = (link.a, link.b);
+            let (pa, pb) = (network.patch(pa), network.patch(pb));
+            let (inner_a, edge) = (ea.points(pa, 1), ea.points(pa, 0));
+            assert_eq!(edge, eb.points(pb, 0));
+            for ((p, e), q) in inner_a.iter().zip(&edge).zip(eb.points(pb, 1)) {
+                let (da, db) = (*e - *p, q - *e);
+                assert!(da.cross(db).length() < 1e-2, "{:?} {} {}", link, da, db);
+                assert!(da.dot(db) >= 0.);
+            }
+        }
+    }
+}
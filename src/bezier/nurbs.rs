@@ -0,0 +1,560 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use anyhow::Context;
+use glam::{vec2, Vec2, Vec3, Vec4};
+
+use crate::bezier::parametric::ParametricSurface;
+use crate::bezier::patch::{tensor_derivatives, BezierPatch, SurfaceDerivatives};
+
+/// Rational tensor-product Bézier patch, storing its control points in homogeneous coordinates
+/// `(w x, w y, w z, w)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RationalPatch {
+    points: Vec<Vec4>,
+    width: usize,
+    height: usize,
+}
+
+impl RationalPatch {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn degree(&self) -> (usize, usize) {
+        (self.width - 1, self.height - 1)
+    }
+
+    pub fn point(&self, i: usize, j: usize) -> Vec3 {
+        let p = self.points[j * self.width + i];
+        p.truncate() / p.w
+    }
+
+    pub fn weight(&self, i: usize, j: usize) -> f32 {
+        self.points[j * self.width + i].w
+    }
+
+    pub fn get_point(&self, u: f32, v: f32) -> Vec3 {
+        self.derivatives(u, v).point
+    }
+
+    /// Evaluates the point along with its first and second order partial derivatives.
+    pub fn derivatives(&self, u: f32, v: f32) -> SurfaceDerivatives {
+        let [a, au, av, auu, auv, avv] =
+            tensor_derivatives(self.points.chunks_exact(self.width), u, v);
+        let w = a.w;
+        let point = a.truncate() / w;
+        let du = (au.truncate() - au.w * point) / w;
+        let dv = (av.truncate() - av.w * point) / w;
+        SurfaceDerivatives {
+            point,
+            du,
+            dv,
+            duu: (auu.truncate() - 2. * au.w * du - auu.w * point) / w,
+            duv: (auv.truncate() - au.w * dv - av.w * du - auv.w * point) / w,
+            dvv: (avv.truncate() - 2. * av.w * dv - avv.w * point) / w,
+        }
+    }
+
+    /// Polynomial patch with the same control points, if all the weights are equal.
+    pub fn to_polynomial(&self) -> Option<BezierPatch> {
+        let w = self.points[0].w;
+        self.points
+            .iter()
+            .all(|p| (p.w - w).abs() <= f32::EPSILON * w)
+            .then(|| {
+                let points = self.points.iter().map(|p| p.truncate() / p.w);
+                BezierPatch::new(self.width, self.height, points).unwrap()
+            })
+    }
+}
+
+impl ParametricSurface for RationalPatch {
+    fn derivatives(&self, u: f32, v: f32) -> SurfaceDerivatives {
+        self.derivatives(u, v)
+    }
+
+    fn degree(&self) -> (usize, usize) {
+        self.degree()
+    }
+}
+
+/// Non-uniform rational B-spline surface.
+///
+/// Control points are stored row-major like in [`BezierPatch`]. Knot vectors must be clamped,
+/// and are rescaled to `[0, 1]`, which is the parameter domain of the surface. The surface is
+/// evaluated through its decomposition into [`RationalPatch`]es, kept up to date on edits.
+#[derive(Debug, Clone)]
+pub struct NurbsSurface {
+    points: Vec<Vec4>,
+    width: usize,
+    height: usize,
+    degree: (usize, usize),
+    knots_u: Vec<f32>,
+    knots_v: Vec<f32>,
+    pieces: Vec<RationalPatch>,
+    breaks_u: Vec<f32>,
+    breaks_v: Vec<f32>,
+}
+
+impl NurbsSurface {
+    /// Non-rational surface with unit weights; see [`Self::with_weights`].
+    pub fn new(
+        degree_u: usize,
+        degree_v: usize,
+        knots_u: Vec<f32>,
+        knots_v: Vec<f32>,
+        width: usize,
+        points: impl IntoIterator<Item = Vec3>,
+    ) -> anyhow::Result<Self> {
+        let points = points.into_iter().map(|p| p.extend(1.)).collect::<Vec<_>>();
+        anyhow::ensure!(
+            width > 0 && !points.is_empty() && points.len() % width == 0,
+            "{} control points do not form rows of {}",
+            points.len(),
+            width
+        );
+        let height = points.len() / width;
+        let knots_u = normalize_knots(knots_u, degree_u, width).context("Invalid knots along u")?;
+        let knots_v =
+            normalize_knots(knots_v, degree_v, height).context("Invalid knots along v")?;
+        let mut surface = Self {
+            points,
+            width,
+            height,
+            degree: (degree_u, degree_v),
+            knots_u,
+            knots_v,
+            pieces: Vec::new(),
+            breaks_u: Vec::new(),
+            breaks_v: Vec::new(),
+        };
+        surface.rebuild();
+        Ok(surface)
+    }
+
+    /// Sets the weights of the control points, in the same order as the points.
+    pub fn with_weights(mut self, weights: impl IntoIterator<Item = f32>) -> anyhow::Result<Self> {
+        let weights = weights.into_iter().collect::<Vec<_>>();
+        anyhow::ensure!(
+            weights.len() == self.points.len(),
+            "Expected {} weights, got {}",
+            self.points.len(),
+            weights.len()
+        );
+        anyhow::ensure!(weights.iter().all(|&w| w > 0.), "Weights must be positive");
+        for (p, w) in self.points.iter_mut().zip(weights) {
+            *p = (p.truncate() / p.w * w).extend(w);
+        }
+        self.rebuild();
+        Ok(self)
+    }
+
+    /// Exact sphere, with `u` going around the `Y` axis and `v` from the south to the north pole.
+    pub fn sphere(center: Vec3, radius: f32) -> Self {
+        let profile = [
+            (vec2(0., -radius), 1.),
+            (vec2(radius, -radius), FRAC_1_SQRT_2),
+            (vec2(radius, 0.), 1.),
+            (vec2(radius, radius), FRAC_1_SQRT_2),
+            (vec2(0., radius), 1.),
+        ];
+        let knots = vec![0., 0., 0., 0.5, 0.5, 1., 1., 1.];
+        Self::around_y(center, &profile, 2, knots)
+    }
+
+    /// Exact open cylinder along the `Y` axis, from `base` up to `base + height * Y`.
+    pub fn cylinder(base: Vec3, radius: f32, height: f32) -> Self {
+        let profile = [(vec2(radius, 0.), 1.), (vec2(radius, height), 1.)];
+        Self::around_y(base, &profile, 1, vec![0., 0., 1., 1.])
+    }
+
+    /// Full revolution around the `Y` axis going through `center` of a profile given as
+    /// `(radius, height)` control points with their weights.
+    fn around_y(center: Vec3, profile: &[(Vec2, f32)], degree: usize, knots: Vec<f32>) -> Self {
+        const S: f32 = FRAC_1_SQRT_2;
+        let circle = [
+            (1., 0., 1.),
+            (1., 1., S),
+            (0., 1., 1.),
+            (-1., 1., S),
+            (-1., 0., 1.),
+            (-1., -1., S),
+            (0., -1., 1.),
+            (1., -1., S),
+            (1., 0., 1.),
+        ];
+        let knots_u = vec![0., 0., 0., 0.25, 0.25, 0.5, 0.5, 0.75, 0.75, 1., 1., 1.];
+        let points = profile.iter().flat_map(|&(p, _)| {
+            circle
+                .iter()
+                .map(move |&(c, s, _)| center + Vec3::new(c * p.x, p.y, -s * p.x))
+        });
+        let weights = profile
+            .iter()
+            .flat_map(|&(_, wp)| circle.iter().map(move |&(_, _, w)| w * wp));
+        Self::new(2, degree, knots_u, knots, circle.len(), points)
+            .and_then(|surface| surface.with_weights(weights))
+            .unwrap()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// B-spline degrees along `u` and `v`.
+    pub fn degrees(&self) -> (usize, usize) {
+        self.degree
+    }
+
+    pub fn knots_u(&self) -> &[f32] {
+        &self.knots_u
+    }
+
+    pub fn knots_v(&self) -> &[f32] {
+        &self.knots_v
+    }
+
+    pub fn point(&self, i: usize, j: usize) -> Vec3 {
+        let p = self.points[self.index(i, j)];
+        p.truncate() / p.w
+    }
+
+    pub fn weight(&self, i: usize, j: usize) -> f32 {
+        self.points[self.index(i, j)].w
+    }
+
+    /// Moves a control point, keeping its weight. Only the surrounding spans change.
+    pub fn set_point(&mut self, i: usize, j: usize, point: Vec3) {
+        let idx = self.index(i, j);
+        let w = self.points[idx].w;
+        self.points[idx] = (point * w).extend(w);
+        self.rebuild();
+    }
+
+    pub fn set_weight(&mut self, i: usize, j: usize, weight: f32) -> anyhow::Result<()> {
+        anyhow::ensure!(weight > 0., "Weights must be positive");
+        let point = self.point(i, j);
+        let idx = self.index(i, j);
+        self.points[idx] = (point * weight).extend(weight);
+        self.rebuild();
+        Ok(())
+    }
+
+    /// Inserts the knot `t` along `u` without changing the shape of the surface.
+    pub fn insert_knot_u(&mut self, t: f32) -> anyhow::Result<()> {
+        self.insert_rows(t)?;
+        self.rebuild();
+        Ok(())
+    }
+
+    /// Inserts the knot `t` along `v` without changing the shape of the surface.
+    pub fn insert_knot_v(&mut self, t: f32) -> anyhow::Result<()> {
+        let mut transposed = self.transposed();
+        transposed.insert_rows(t)?;
+        *self = transposed.transposed();
+        self.rebuild();
+        Ok(())
+    }
+
+    /// Rational Bézier patches making up the surface, row-major over the knot spans.
+    pub fn bezier_patches(&self) -> &[RationalPatch] {
+        &self.pieces
+    }
+
+    /// Number of non-empty knot spans along `u` and `v`.
+    pub fn spans(&self) -> (usize, usize) {
+        (self.breaks_u.len() - 1, self.breaks_v.len() - 1)
+    }
+
+    pub fn get_point(&self, u: f32, v: f32) -> Vec3 {
+        let (piece, s, t, _) = self.locate(u, v);
+        piece.get_point(s, t)
+    }
+
+    pub fn derivatives(&self, u: f32, v: f32) -> SurfaceDerivatives {
+        let (piece, s, t, scale) = self.locate(u, v);
+        let d = piece.derivatives(s, t);
+        SurfaceDerivatives {
+            point: d.point,
+            du: d.du * scale.x,
+            dv: d.dv * scale.y,
+            duu: d.duu * scale.x * scale.x,
+            duv: d.duv * scale.x * scale.y,
+            dvv: d.dvv * scale.y * scale.y,
+        }
+    }
+
+    /// Bézier piece containing `(u, v)`, the local parameters within it and the derivative
+    /// scaling from local to global parameters.
+    fn locate(&self, u: f32, v: f32) -> (&RationalPatch, f32, f32, Vec2) {
+        let span = |breaks: &[f32], t: f32| {
+            let k = breaks
+                .partition_point(|&b| b <= t)
+                .saturating_sub(1)
+                .min(breaks.len() - 2);
+            let (t0, t1) = (breaks[k], breaks[k + 1]);
+            (k, (t - t0) / (t1 - t0), 1. / (t1 - t0))
+        };
+        let (i, s, scale_u) = span(&self.breaks_u, u);
+        let (j, t, scale_v) = span(&self.breaks_v, v);
+        let piece = &self.pieces[j * (self.breaks_u.len() - 1) + i];
+        (piece, s, t, vec2(scale_u, scale_v))
+    }
+
+    /// Boehm knot insertion along `u`; the Bézier pieces are left untouched.
+    fn insert_rows(&mut self, t: f32) -> anyhow::Result<()> {
+        let p = self.degree.0;
+        let knots = &self.knots_u;
+        anyhow::ensure!(
+            t > 0. && t < 1.,
+            "Knot {} is outside of the interior of the domain",
+            t
+        );
+        let multiplicity = knots.iter().filter(|&&k| k == t).count();
+        anyhow::ensure!(
+            multiplicity < p,
+            "Knot {} already has the maximum multiplicity {}",
+            t,
+            multiplicity
+        );
+        let k = knots.partition_point(|&x| x <= t) - 1;
+        let mut points = Vec::with_capacity(self.points.len() + self.height);
+        for row in self.points.chunks_exact(self.width) {
+            points.extend((0..=self.width).map(|i| {
+                if i + p <= k {
+                    row[i]
+                } else if i > k {
+                    row[i - 1]
+                } else {
+                    let alpha = (t - knots[i]) / (knots[i + p] - knots[i]);
+                    row[i - 1].lerp(row[i], alpha)
+                }
+            }));
+        }
+        self.points = points;
+        self.knots_u.insert(k + 1, t);
+        self.width += 1;
+        Ok(())
+    }
+
+    /// Same surface with `u` and `v` swapped, without its Bézier pieces.
+    fn transposed(&self) -> Self {
+        let points = (0..self.width)
+            .flat_map(|i| (0..self.height).map(move |j| (i, j)))
+            .map(|(i, j)| self.points[j * self.width + i])
+            .collect();
+        Self {
+            points,
+            width: self.height,
+            height: self.width,
+            degree: (self.degree.1, self.degree.0),
+            knots_u: self.knots_v.clone(),
+            knots_v: self.knots_u.clone(),
+            pieces: Vec::new(),
+            breaks_u: Vec::new(),
+            breaks_v: Vec::new(),
+        }
+    }
+
+    /// Recomputes the Bézier decomposition by raising every interior knot to full multiplicity.
+    fn rebuild(&mut self) {
+        let saturate = |surface: &mut Self| {
+            let mut breaks = surface.knots_u.clone();
+            breaks.dedup();
+            for &t in &breaks[1..breaks.len() - 1] {
+                while surface.knots_u.iter().filter(|&&k| k == t).count() < surface.degree.0 {
+                    surface.insert_rows(t).unwrap();
+                }
+            }
+            breaks
+        };
+        let mut transposed = self.transposed();
+        let breaks_v = saturate(&mut transposed);
+        let mut bezier = transposed.transposed();
+        let breaks_u = saturate(&mut bezier);
+
+        let (pu, pv) = self.degree;
+        let spans_u = breaks_u.len() - 1;
+        debug_assert_eq!(bezier.width, pu * spans_u + 1);
+        self.pieces = (0..breaks_v.len() - 1)
+            .flat_map(|sv| (0..spans_u).map(move |su| (su, sv)))
+            .map(|(su, sv)| RationalPatch {
+                points: (sv * pv..=sv * pv + pv)
+                    .flat_map(|j| (su * pu..=su * pu + pu).map(move |i| (i, j)))
+                    .map(|(i, j)| bezier.points[j * bezier.width + i])
+                    .collect(),
+                width: pu + 1,
+                height: pv + 1,
+            })
+            .collect();
+        self.breaks_u = breaks_u;
+        self.breaks_v = breaks_v;
+    }
+
+    fn index(&self, i: usize, j: usize) -> usize {
+        assert!(
+            i < self.width && j < self.height,
+            "Control point ({}, {}) out of bounds of {}x{} grid",
+            i,
+            j,
+            self.width,
+            self.height
+        );
+        j * self.width + i
+    }
+}
+
+impl TryFrom<&BezierPatch> for NurbsSurface {
+    type Error = anyhow::Error;
+
+    fn try_from(patch: &BezierPatch) -> Result<Self, Self::Error> {
+        let clamped = |degree: usize| [vec![0.; degree + 1], vec![1.; degree + 1]].concat();
+        let (deg_u, deg_v) = patch.degree();
+        Self::new(
+            deg_u,
+            deg_v,
+            clamped(deg_u),
+            clamped(deg_v),
+            patch.width(),
+            patch.points().iter().copied(),
+        )
+    }
+}
+
+impl ParametricSurface for NurbsSurface {
+    fn derivatives(&self, u: f32, v: f32) -> SurfaceDerivatives {
+        self.derivatives(u, v)
+    }
+
+    fn get_point(&self, u: f32, v: f32) -> Vec3 {
+        self.get_point(u, v)
+    }
+
+    fn degree(&self) -> (usize, usize) {
+        let (spans_u, spans_v) = self.spans();
+        (self.degree.0 * spans_u, self.degree.1 * spans_v)
+    }
+
+    fn breaks(&self) -> (Vec<f32>, Vec<f32>) {
+        (self.breaks_u.clone(), self.breaks_v.clone())
+    }
+
+    fn is_closed_u(&self) -> bool {
+        (0..self.height).all(|j| {
+            self.point(0, j)
+                .abs_diff_eq(self.point(self.width - 1, j), 1e-4)
+        })
+    }
+
+    fn is_closed_v(&self) -> bool {
+        (0..self.width).all(|i| {
+            self.point(i, 0)
+                .abs_diff_eq(self.point(i, self.height - 1), 1e-4)
+        })
+    }
+}
+
+/// Validates a clamped knot vector for `count` control points, and rescales it to `[0, 1]`.
+fn normalize_knots(knots: Vec<f32>, degree: usize, count: usize) -> anyhow::Result<Vec<f32>> {
+    anyhow::ensure!(degree > 0, "Degree must be at least 1");
+    anyhow::ensure!(
+        count > degree,
+        "Degree {} needs at least {} control points, got {}",
+        degree,
+        degree + 1,
+        count
+    );
+    anyhow::ensure!(
+        knots.len() == count + degree + 1,
+        "Expected {} knots, got {}",
+        count + degree + 1,
+        knots.len()
+    );
+    anyhow::ensure!(
+        knots.windows(2).all(|w| w[0] <= w[1]),
+        "Knots are not sorted"
+    );
+    let (first, last) = (knots[0], knots[knots.len() - 1]);
+    anyhow::ensure!(first < last, "Knot vector has an empty domain");
+    anyhow::ensure!(
+        knots[..=degree].iter().all(|&k| k == first)
+            && knots[knots.len() - degree - 1..].iter().all(|&k| k == last),
+        "Knot vector is not clamped"
+    );
+    anyhow::ensure!(
+        knots[degree + 1..knots.len() - degree - 1]
+            .iter()
+            .all(|&t| knots.iter().filter(|&&k| k == t).count() <= degree),
+        "Interior knots cannot have a multiplicity above the degree"
+    );
+    Ok(knots
+        .into_iter()
+        .map(|k| (k - first) / (last - first))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Vec3};
+    use test_log::test;
+
+    use super::NurbsSurface;
+    use crate::bezier::parametric::ParametricSurface;
+    use crate::bezier::patch::BezierPatch;
+    use crate::bezier::tessellation::TessellationBuilder;
+
+    #[test]
+    fn sphere_is_exact() {
+        let center = vec3(1., 2., 3.);
+        let sphere = NurbsSurface::sphere(center, 2.);
+        assert_eq!(sphere.spans(), (4, 2));
+        assert!(sphere.is_closed_u() && !sphere.is_closed_v());
+        for (u, v) in [(0., 0.5), (0.1, 0.3), (0.4, 0.9), (0.77, 0.61), (1., 0.2)] {
+            let point = sphere.get_point(u, v);
+            assert!(((point - center).length() - 2.).abs() < 1e-5);
+            let normal = ParametricSurface::normal(&sphere, u, v);
+            assert!((normal - (point - center) / 2.).length() < 1e-4);
+        }
+
+        let data = TessellationBuilder::new(9, 9).build(&sphere).unwrap();
+        for vertex in data.vertices {
+            assert!(((vertex.position - center).length() - 2.).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn knot_insertion_keeps_shape() {
+        let patch = BezierPatch::from_rows([
+            [Vec3::ZERO, vec3(1., 1., 0.), vec3(2., 0., 0.)],
+            [vec3(0., 1., 1.), vec3(1., 2., 1.), vec3(2., 0., 1.)],
+        ])
+        .unwrap();
+        let mut surface = NurbsSurface::try_from(&patch).unwrap();
+        let cylinder = NurbsSurface::cylinder(Vec3::ZERO, 1., 2.);
+        let mut edited = cylinder.clone();
+        surface.insert_knot_u(0.3).unwrap();
+        surface.insert_knot_u(0.3).unwrap();
+        assert!(surface.insert_knot_u(0.3).is_err());
+        edited.insert_knot_v(0.6).unwrap();
+        assert_eq!(surface.spans(), (2, 1));
+        assert_eq!(edited.height(), 3);
+        for (u, v) in [(0., 0.), (0.2, 0.7), (0.3, 0.5), (0.8, 0.1), (1., 1.)] {
+            assert!((surface.get_point(u, v) - patch.get_point(u, v)).length() < 1e-5);
+            assert!((edited.get_point(u, v) - cylinder.get_point(u, v)).length() < 1e-5);
+        }
+
+        // Moving a control point only changes the spans it supports
+        edited.set_point(0, 0, vec3(1., -1., 0.));
+        let (near, far) = (0.1, 0.7);
+        assert!((edited.get_point(near, 0.1) - cylinder.get_point(near, 0.1)).length() > 1e-3);
+        assert!((edited.get_point(far, 0.1) - cylinder.get_point(far, 0.1)).length() < 1e-5);
+    }
+}
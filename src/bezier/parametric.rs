@@ -0,0 +1,105 @@
+use glam::Vec3;
+use rayon::prelude::*;
+
+use crate::bezier::curvature::Curvature;
+use crate::bezier::integration;
+use crate::bezier::patch::SurfaceDerivatives;
+use crate::bezier::trim::Trim;
+use crate::mass::MassProperties;
+
+/// Surface parameterized over `[0, 1]²`.
+///
+/// Implemented by every surface kind that can be tessellated with a
+/// [`TessellationBuilder`](crate::bezier::tessellation::TessellationBuilder).
+pub trait ParametricSurface {
+    /// Point and partial derivatives at `(u, v)`.
+    fn derivatives(&self, u: f32, v: f32) -> SurfaceDerivatives;
+
+    /// Points and derivatives over the grid of parameters `us` × `vs`, row-major along `u`.
+    ///
+    /// Evaluates each point on its own, in parallel; surfaces able to share work along rows and
+    /// columns should override it.
+    fn grid_derivatives(&self, us: &[f32], vs: &[f32]) -> Vec<SurfaceDerivatives>
+    where
+        Self: Sync,
+    {
+        vs.par_iter()
+            .flat_map_iter(|&v| us.iter().map(move |&u| self.derivatives(u, v)))
+            .collect()
+    }
+
+    fn get_point(&self, u: f32, v: f32) -> Vec3 {
+        self.derivatives(u, v).point
+    }
+
+    /// Unit normal `du × dv`.
+    ///
+    /// Where the surface is degenerate (collapsed edge or parallel partials), the normal is taken
+    /// slightly inside the domain, moving toward its center until the partials become
+    /// independent. It is zero only if they never do.
+    fn normal(&self, u: f32, v: f32) -> Vec3 {
+        nudged_normal(|u, v| self.derivatives(u, v), u, v, 1e-4)
+    }
+
+    /// Principal, Gaussian and mean curvatures at `(u, v)`.
+    fn curvature(&self, u: f32, v: f32) -> Curvature {
+        Curvature::new(&self.derivatives(u, v), self.normal(u, v))
+    }
+
+    /// Area, and volume properties if the surface is a closed shell, integrated over the surface.
+    fn mass_properties(&self) -> MassProperties {
+        integration::mass_properties(self)
+    }
+
+    /// Polynomial degree of the surface along `u` and `v`, or of its pieces times their count
+    /// for piecewise surfaces.
+    fn degree(&self) -> (usize, usize);
+
+    /// Parameters along `u` and `v` where piecewise surfaces join their pieces, from `0` to `1`
+    /// included. Derivatives may jump there.
+    fn breaks(&self) -> (Vec<f32>, Vec<f32>) {
+        (vec![0., 1.], vec![0., 1.])
+    }
+
+    /// Whether the edges `u = 0` and `u = 1` coincide.
+    fn is_closed_u(&self) -> bool {
+        false
+    }
+
+    /// Whether the edges `v = 0` and `v = 1` coincide.
+    fn is_closed_v(&self) -> bool {
+        false
+    }
+
+    /// Part of the parameter domain to keep, if not all of it.
+    fn trim(&self) -> Option<&Trim> {
+        None
+    }
+}
+
+/// Unit normal `du × dv` at `(u, v)`.
+///
+/// Where the surface is degenerate (collapsed edge or parallel partials), the normal is taken
+/// slightly inside the domain, moving toward its center until the partials become independent.
+/// It is zero only if they never do.
+pub(crate) fn nudged_normal(
+    derivatives: impl Fn(f32, f32) -> SurfaceDerivatives,
+    u: f32,
+    v: f32,
+    precision: f32,
+) -> Vec3 {
+    let raw_normal = |u: f32, v: f32| {
+        let SurfaceDerivatives { du, dv, .. } = derivatives(u, v);
+        let normal = du.cross(dv);
+        let threshold = precision * du.length() * dv.length();
+        (normal.length() > threshold).then(|| normal.normalize())
+    };
+    let mut step = precision;
+    let (su, sv) = ((0.5 - u).signum(), (0.5 - v).signum());
+    let mut normal = raw_normal(u, v);
+    while normal.is_none() && step < 0.5 {
+        normal = raw_normal(u + su * step, v + sv * step);
+        step *= 2.;
+    }
+    normal.unwrap_or(Vec3::ZERO)
+}
@@ -0,0 +1,621 @@
+use std::ops::{Mul, Range, Sub};
+
+use anyhow::Context;
+use glam::Vec3;
+use rayon::prelude::*;
+
+use crate::bezier::basis::BasisTable;
+use crate::bezier::curve::{BezierCurve, Lerp};
+
+/// Tensor-product Bézier patch defined by a `width` × `height` grid of control points.
+///
+/// Control points are stored row-major: a row holds the `width` control points of a curve along
+/// `u`, and the `height` rows are blended together along `v`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BezierPatch {
+    points: Vec<Vec3>,
+    width: usize,
+    height: usize,
+}
+
+impl BezierPatch {
+    pub fn new(
+        width: usize,
+        height: usize,
+        points: impl IntoIterator<Item = Vec3>,
+    ) -> anyhow::Result<Self> {
+        let points = points.into_iter().collect::<Vec<_>>();
+        anyhow::ensure!(
+            width > 0 && height > 0,
+            "Control grid cannot be empty (got {}x{})",
+            width,
+            height
+        );
+        anyhow::ensure!(
+            points.len() == width * height,
+            "Control grid of size {}x{} needs {} points, got {}",
+            width,
+            height,
+            width * height,
+            points.len()
+        );
+        Ok(Self {
+            points,
+            width,
+            height,
+        })
+    }
+
+    pub fn from_rows<R: IntoIterator<Item = Vec3>>(
+        rows: impl IntoIterator<Item = R>,
+    ) -> anyhow::Result<Self> {
+        let mut width = None;
+        let mut height = 0;
+        let mut points = Vec::new();
+        for row in rows {
+            let len = points.len();
+            points.extend(row);
+            let row_width = points.len() - len;
+            let width = *width.get_or_insert(row_width);
+            anyhow::ensure!(
+                row_width == width,
+                "Row {} has {} control points, expected {}",
+                height,
+                row_width,
+                width
+            );
+            height += 1;
+        }
+        Self::new(width.context("Control grid has no rows")?, height, points)
+    }
+
+    pub fn from_profile(profile: &[BezierCurve<Vec3>]) -> anyhow::Result<Self> {
+        Self::from_rows(profile.iter().map(|curve| curve.control_points()))
+            .context("Invalid surface profile")
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn degree_u(&self) -> usize {
+        self.width - 1
+    }
+
+    pub fn degree_v(&self) -> usize {
+        self.height - 1
+    }
+
+    pub fn degree(&self) -> (usize, usize) {
+        (self.degree_u(), self.degree_v())
+    }
+
+    pub fn points(&self) -> &[Vec3] {
+        &self.points
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> Vec3 {
+        self.points[self.index(i, j)]
+    }
+
+    pub fn get_mut(&mut self, i: usize, j: usize) -> &mut Vec3 {
+        let idx = self.index(i, j);
+        &mut self.points[idx]
+    }
+
+    pub fn row(&self, j: usize) -> &[Vec3] {
+        &self.points[j * self.width..(j + 1) * self.width]
+    }
+
+    pub fn row_mut(&mut self, j: usize) -> &mut [Vec3] {
+        &mut self.points[j * self.width..(j + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[Vec3]> {
+        self.points.chunks_exact(self.width)
+    }
+
+    pub fn column(&self, i: usize) -> impl Iterator<Item = Vec3> + '_ {
+        assert!(i < self.width, "Column index out of bounds");
+        self.points.iter().skip(i).step_by(self.width).copied()
+    }
+
+    pub fn column_mut(&mut self, i: usize) -> impl Iterator<Item = &mut Vec3> {
+        assert!(i < self.width, "Column index out of bounds");
+        self.points.iter_mut().skip(i).step_by(self.width)
+    }
+
+    /// Control row `j` as a curve along `u`.
+    pub fn row_curve(&self, j: usize) -> BezierCurve<Vec3> {
+        BezierCurve::new(self.row(j).iter().copied())
+    }
+
+    /// Control column `i` as a curve along `v`.
+    pub fn column_curve(&self, i: usize) -> BezierCurve<Vec3> {
+        BezierCurve::new(self.column(i))
+    }
+
+    /// Axis-aligned box containing the control points, and therefore the whole patch.
+    pub fn bounding_box(&self) -> (Vec3, Vec3) {
+        self.points.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), &p| (min.min(p), max.max(p)),
+        )
+    }
+
+    /// Closes the patch along `v` by appending the rows `2 R0 - R1` and `R0`, so that the last row
+    /// meets the first with the same tangent, as [`BezierCurve::control_points`] does for curves.
+    pub fn closed_v(&self) -> Self {
+        if self.height < 2 {
+            return self.clone();
+        }
+        let mut points = self.points.clone();
+        points.extend(
+            self.row(0)
+                .iter()
+                .zip(self.row(1))
+                .map(|(&p0, &p1)| 2. * p0 - p1),
+        );
+        points.extend_from_slice(self.row(0));
+        Self {
+            points,
+            width: self.width,
+            height: self.height + 2,
+        }
+    }
+
+    /// Closes the patch along `u`, see [`Self::closed_v`].
+    pub fn closed_u(&self) -> Self {
+        self.transpose().closed_v().transpose()
+    }
+
+    /// Whether the first and last columns coincide, making the surface closed along `u`.
+    pub fn is_closed_u(&self, epsilon: f32) -> bool {
+        self.width > 1
+            && self
+                .column(0)
+                .zip(self.column(self.width - 1))
+                .all(|(a, b)| a.distance(b) <= epsilon)
+    }
+
+    /// Whether the first and last rows coincide, making the surface closed along `v`.
+    pub fn is_closed_v(&self, epsilon: f32) -> bool {
+        self.height > 1
+            && self
+                .row(0)
+                .iter()
+                .zip(self.row(self.height - 1))
+                .all(|(a, b)| a.distance(*b) <= epsilon)
+    }
+
+    /// Same surface with rows elevated to the given degree along `u`.
+    pub fn elevate_u_to(&self, degree: usize) -> Self {
+        let rows = self
+            .rows()
+            .map(|row| BezierCurve::new(row.iter().copied()).elevate_to(degree));
+        // Rows all have the same length once elevated
+        Self::from_rows(rows.map(|curve| curve.control_points())).unwrap()
+    }
+
+    /// Same surface with columns elevated to the given degree along `v`.
+    pub fn elevate_v_to(&self, degree: usize) -> Self {
+        self.transpose().elevate_u_to(degree).transpose()
+    }
+
+    /// Swaps the `u` and `v` parameter directions.
+    pub fn transpose(&self) -> Self {
+        Self {
+            points: (0..self.width).flat_map(|i| self.column(i)).collect(),
+            width: self.height,
+            height: self.width,
+        }
+    }
+
+    /// Evaluates the patch by first reducing each row along `u`, then the result along `v`.
+    pub fn get_point(&self, u: f32, v: f32) -> Vec3 {
+        let mut row = Vec::with_capacity(self.width);
+        let mut column = self
+            .rows()
+            .map(|points| {
+                row.clear();
+                row.extend_from_slice(points);
+                casteljau(&mut row, u)
+            })
+            .collect::<Vec<_>>();
+        casteljau(&mut column, v)
+    }
+
+    /// Evaluates the patch by first reducing each column along `v`, then the result along `u`.
+    ///
+    /// Mathematically equivalent to [`Self::get_point`].
+    pub fn get_point_vu(&self, u: f32, v: f32) -> Vec3 {
+        let mut column = Vec::with_capacity(self.height);
+        let mut row = (0..self.width)
+            .map(|i| {
+                column.clear();
+                column.extend(self.column(i));
+                casteljau(&mut column, v)
+            })
+            .collect::<Vec<_>>();
+        casteljau(&mut row, u)
+    }
+
+    /// Evaluates the point along with its first and second order partial derivatives.
+    pub fn derivatives(&self, u: f32, v: f32) -> SurfaceDerivatives {
+        let [point, du, dv, duu, duv, dvv] = tensor_derivatives(self.rows(), u, v);
+        SurfaceDerivatives {
+            point,
+            du,
+            dv,
+            duu,
+            duv,
+            dvv,
+        }
+    }
+
+    /// Points and derivatives over the grid of parameters of the tables, row-major along `u`.
+    ///
+    /// Each row first reduces the control grid along `v` with the basis values tabulated there,
+    /// which leaves sums over a single row of control points for each of its samples. Rows are
+    /// evaluated in parallel.
+    pub fn grid_derivatives(
+        &self,
+        u: &BasisTable,
+        v: &BasisTable,
+    ) -> anyhow::Result<Vec<SurfaceDerivatives>> {
+        anyhow::ensure!(
+            (u.degree(), v.degree()) == self.degree(),
+            "Basis tables of degree ({}, {}) do not match patch of degree {:?}",
+            u.degree(),
+            v.degree(),
+            self.degree()
+        );
+        let rows = (0..v.len()).into_par_iter().flat_map_iter(|j| {
+            // Control grid reduced along `v`, with its first and second derivatives
+            let reduced = v.get(j).map(|basis| {
+                (0..self.width)
+                    .map(|i| {
+                        self.column(i)
+                            .zip(basis)
+                            .fold(Vec3::ZERO, |sum, (point, &b)| sum + b * point)
+                    })
+                    .collect::<Vec<_>>()
+            });
+            (0..u.len()).map(move |k| {
+                let [basis, first, second] = u.get(k);
+                let sum = |weights: &[f32], points: &[Vec3]| {
+                    weights
+                        .iter()
+                        .zip(points)
+                        .fold(Vec3::ZERO, |sum, (&w, &p)| sum + w * p)
+                };
+                SurfaceDerivatives {
+                    point: sum(basis, &reduced[0]),
+                    du: sum(first, &reduced[0]),
+                    dv: sum(basis, &reduced[1]),
+                    duu: sum(second, &reduced[0]),
+                    duv: sum(first, &reduced[1]),
+                    dvv: sum(basis, &reduced[2]),
+                }
+            })
+        });
+        Ok(rows.collect())
+    }
+
+    /// Splits the patch at `u = t` into the sub-patches over `[0, t]` and `[t, 1]`.
+    pub fn split_u(&self, t: f32) -> (Self, Self) {
+        let (mut first, mut second) = (Vec::new(), Vec::new());
+        for row in self.rows() {
+            let (left, right) = split_polygon(row, t);
+            first.extend(left);
+            second.extend(right);
+        }
+        let patch = |points| Self {
+            points,
+            width: self.width,
+            height: self.height,
+        };
+        (patch(first), patch(second))
+    }
+
+    /// Splits the patch at `v = t`, see [`Self::split_u`].
+    pub fn split_v(&self, t: f32) -> (Self, Self) {
+        let (first, second) = self.transpose().split_u(t);
+        (first.transpose(), second.transpose())
+    }
+
+    /// Patch covering exactly the `u` × `v` rectangle of this one, reparameterized over `[0, 1]²`.
+    /// Both ranges must be non-empty and within `[0, 1]`.
+    pub fn sub_patch(&self, u: Range<f32>, v: Range<f32>) -> anyhow::Result<Self> {
+        for range in [&u, &v] {
+            anyhow::ensure!(
+                0. <= range.start && range.start < range.end && range.end <= 1.,
+                "Sub-patch range {:?} is empty or not within [0, 1]",
+                range
+            );
+        }
+        let (patch, _) = self.split_u(u.end);
+        let (_, patch) = patch.split_u(u.start / u.end);
+        let (patch, _) = patch.split_v(v.end);
+        let (_, patch) = patch.split_v(v.start / v.end);
+        Ok(patch)
+    }
+
+    /// Curve along `v` traced at fixed `u`.
+    pub fn iso_u(&self, u: f32) -> BezierCurve<Vec3> {
+        let mut row = Vec::with_capacity(self.width);
+        BezierCurve::new(self.rows().map(|points| {
+            row.clear();
+            row.extend_from_slice(points);
+            casteljau(&mut row, u)
+        }))
+    }
+
+    /// Curve along `u` traced at fixed `v`.
+    pub fn iso_v(&self, v: f32) -> BezierCurve<Vec3> {
+        self.transpose().iso_u(v)
+    }
+
+    fn index(&self, i: usize, j: usize) -> usize {
+        assert!(
+            i < self.width && j < self.height,
+            "Control point ({}, {}) out of bounds of {}x{} grid",
+            i,
+            j,
+            self.width,
+            self.height
+        );
+        j * self.width + i
+    }
+}
+
+impl TryFrom<&[BezierCurve<Vec3>]> for BezierPatch {
+    type Error = anyhow::Error;
+
+    fn try_from(profile: &[BezierCurve<Vec3>]) -> Result<Self, Self::Error> {
+        Self::from_profile(profile)
+    }
+}
+
+/// Point and partial derivatives of a surface at a given parameter.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SurfaceDerivatives {
+    pub point: Vec3,
+    pub du: Vec3,
+    pub dv: Vec3,
+    pub duu: Vec3,
+    pub duv: Vec3,
+    pub dvv: Vec3,
+}
+
+/// In-place de Casteljau reduction; the buffer is left in an unspecified state.
+pub(crate) fn casteljau<V: Lerp<f32> + Copy>(points: &mut [V], t: f32) -> V {
+    for len in (1..points.len()).rev() {
+        for k in 0..len {
+            points[k] = points[k].lerp(points[k + 1], t);
+        }
+    }
+    points[0]
+}
+
+/// Start of the range relative to `[0, range.end]`, where it lies once the patch is cut at its end.
+/// Control polygons of the two halves of the curve split at `t`.
+pub(crate) fn split_polygon<V: Lerp<f32> + Copy>(points: &[V], t: f32) -> (Vec<V>, Vec<V>) {
+    let mut buffer = points.to_vec();
+    let mut left = Vec::with_capacity(points.len());
+    let mut right = Vec::with_capacity(points.len());
+    for len in (0..points.len()).rev() {
+        left.push(buffer[0]);
+        right.push(buffer[len]);
+        for k in 0..len {
+            buffer[k] = buffer[k].lerp(buffer[k + 1], t);
+        }
+    }
+    right.reverse();
+    (left, right)
+}
+
+/// In-place de Casteljau reduction returning the point, first and second derivatives at `t`.
+pub(crate) fn casteljau_derivatives<V>(points: &mut [V], t: f32) -> [V; 3]
+where
+    V: Lerp<f32> + Copy + Default + Sub<Output = V> + Mul<f32, Output = V>,
+{
+    let degree = points.len() - 1;
+    for len in (3..points.len()).rev() {
+        for k in 0..len {
+            points[k] = points[k].lerp(points[k + 1], t);
+        }
+    }
+    match *points {
+        [p0] => [p0, V::default(), V::default()],
+        [p0, p1] => [p0.lerp(p1, t), p1 - p0, V::default()],
+        [q0, q1, q2, ..] => {
+            let n = degree as f32;
+            let (r0, r1) = (q0.lerp(q1, t), q1.lerp(q2, t));
+            [
+                r0.lerp(r1, t),
+                (r1 - r0) * n,
+                ((q2 - q1) - (q1 - q0)) * (n * (n - 1.)),
+            ]
+        }
+        [] => unreachable!("Empty control polygon"),
+    }
+}
+
+/// Point, `du`, `dv`, `duu`, `duv` and `dvv` of the tensor-product patch with the given rows of
+/// control points.
+pub(crate) fn tensor_derivatives<'a, V>(
+    rows: impl Iterator<Item = &'a [V]>,
+    u: f32,
+    v: f32,
+) -> [V; 6]
+where
+    V: 'a + Lerp<f32> + Copy + Default + Sub<Output = V> + Mul<f32, Output = V>,
+{
+    let mut row = Vec::new();
+    let (mut points, mut du, mut duu) = (Vec::new(), Vec::new(), Vec::new());
+    for control in rows {
+        row.clear();
+        row.extend_from_slice(control);
+        let [p, d1, d2] = casteljau_derivatives(&mut row, u);
+        points.push(p);
+        du.push(d1);
+        duu.push(d2);
+    }
+    let [point, dv, dvv] = casteljau_derivatives(&mut points, v);
+    let [du, duv, _] = casteljau_derivatives(&mut du, v);
+    let [duu, _, _] = casteljau_derivatives(&mut duu, v);
+    [point, du, dv, duu, duv, dvv]
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Vec3};
+    use test_log::test;
+
+    use super::BezierPatch;
+    use crate::bezier::basis::BasisTable;
+    use crate::bezier::curve::BezierCurve;
+
+    #[test]
+    fn profile_validation() {
+        let ok = [
+            BezierCurve::new([Vec3::ZERO, Vec3::X]),
+            BezierCurve::new([Vec3::Z, Vec3::X + Vec3::Z]),
+        ];
+        let patch = BezierPatch::from_profile(&ok).unwrap();
+        assert_eq!(patch.degree(), (1, 1));
+
+        let mismatched = [
+            BezierCurve::new([Vec3::ZERO, Vec3::X]),
+            BezierCurve::new([Vec3::Z, Vec3::Y, Vec3::X]),
+        ];
+        assert!(BezierPatch::from_profile(&mismatched).is_err());
+        assert!(BezierPatch::new(2, 2, [Vec3::ZERO; 3]).is_err());
+    }
+
+    #[test]
+    fn evaluation_order() {
+        let patch = BezierPatch::from_rows([
+            [vec3(0., 0., 0.), vec3(1., 1., 0.), vec3(2., 0., 0.)],
+            [vec3(0., 1., 1.), vec3(1., 3., 1.), vec3(2., -1., 1.)],
+        ])
+        .unwrap();
+        assert_eq!(patch.get_point(0., 0.), Vec3::ZERO);
+        assert_eq!(patch.get_point(1., 1.), vec3(2., -1., 1.));
+        for (u, v) in [(0.25, 0.5), (0.7, 0.1), (0.5, 0.9)] {
+            let diff = patch.get_point(u, v) - patch.get_point_vu(u, v);
+            assert!(diff.length() < 1e-6);
+        }
+        let transposed = patch.transpose();
+        assert_eq!(transposed.degree(), (1, 2));
+        let diff = transposed.get_point(0.3, 0.6) - patch.get_point(0.6, 0.3);
+        assert!(diff.length() < 1e-6);
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        let patch = BezierPatch::from_rows([
+            [
+                vec3(0., 0., 0.),
+                vec3(1., 1., 0.),
+                vec3(2., 0., 0.),
+                vec3(3., 2., 0.),
+            ],
+            [
+                vec3(0., 1., 1.),
+                vec3(1., 3., 1.),
+                vec3(2., -1., 1.),
+                vec3(3., 0., 1.),
+            ],
+            [
+                vec3(0., 0., 2.),
+                vec3(1., 2., 2.),
+                vec3(2., 1., 2.),
+                vec3(3., 1., 2.),
+            ],
+        ])
+        .unwrap();
+        let h = 1e-2;
+        let (u, v) = (0.4, 0.3);
+        let d = patch.derivatives(u, v);
+        let fd_u = (patch.get_point(u + h, v) - patch.get_point(u - h, v)) / (2. * h);
+        let fd_v = (patch.get_point(u, v + h) - patch.get_point(u, v - h)) / (2. * h);
+        let fd_uu =
+            (patch.get_point(u + h, v) - 2. * d.point + patch.get_point(u - h, v)) / (h * h);
+        let fd_uv = (patch.derivatives(u, v + h).du - patch.derivatives(u, v - h).du) / (2. * h);
+        assert!((d.point - patch.get_point(u, v)).length() < 1e-5);
+        assert!((d.du - fd_u).length() < 1e-2);
+        assert!((d.dv - fd_v).length() < 1e-2);
+        assert!((d.duu - fd_uu).length() < 1e-1);
+        assert!((d.duv - fd_uv).length() < 1e-2);
+
+        // Tabulated evaluation over a grid agrees with pointwise evaluation
+        let (us, vs) = ([0., 0.4, 1.], [0.3, 0.8]);
+        let grid = patch
+            .grid_derivatives(&BasisTable::new(3, &us), &BasisTable::new(2, &vs))
+            .unwrap();
+        for (k, g) in grid.iter().enumerate() {
+            let d = patch.derivatives(us[k % 3], vs[k / 3]);
+            for (a, b) in [
+                (g.point, d.point),
+                (g.du, d.du),
+                (g.dv, d.dv),
+                (g.duu, d.duu),
+                (g.duv, d.duv),
+                (g.dvv, d.dvv),
+            ] {
+                assert!((a - b).length() < 1e-4);
+            }
+        }
+        assert!(patch
+            .grid_derivatives(&BasisTable::new(2, &us), &BasisTable::new(2, &vs))
+            .is_err());
+    }
+
+    #[test]
+    fn subdivision() {
+        let patch = BezierPatch::from_rows([
+            [vec3(0., 0., 0.), vec3(1., 1., 0.), vec3(2., 0., 0.)],
+            [vec3(0., 1., 1.), vec3(1., 3., 1.), vec3(2., -1., 1.)],
+        ])
+        .unwrap();
+        let (left, right) = patch.split_u(0.3);
+        let (bottom, top) = patch.split_v(0.6);
+        for (u, v) in [(0., 0.), (0.25, 0.5), (0.7, 0.1), (1., 1.)] {
+            let close = |a: Vec3, b: Vec3| (a - b).length() < 1e-5;
+            assert!(close(left.get_point(u, v), patch.get_point(0.3 * u, v)));
+            assert!(close(
+                right.get_point(u, v),
+                patch.get_point(0.3 + 0.7 * u, v)
+            ));
+            assert!(close(bottom.get_point(u, v), patch.get_point(u, 0.6 * v)));
+            assert!(close(
+                top.get_point(u, v),
+                patch.get_point(u, 0.6 + 0.4 * v)
+            ));
+
+            let sub = patch.sub_patch(0.2..0.5, 0.4..0.9).unwrap();
+            let expected = patch.get_point(0.2 + 0.3 * u, 0.4 + 0.5 * v);
+            assert!(close(sub.get_point(u, v), expected));
+
+            assert!(close(patch.iso_u(u).get_point(v), patch.get_point(u, v)));
+            assert!(close(patch.iso_v(v).get_point(u), patch.get_point(u, v)));
+        }
+        assert_eq!(patch.iso_u(0.5).len(), patch.height());
+    }
+
+    #[test]
+    fn sub_patch_rejects_invalid_ranges() {
+        let patch =
+            BezierPatch::from_rows([[Vec3::ZERO, Vec3::X], [Vec3::Z, Vec3::X + Vec3::Z]]).unwrap();
+        assert!(patch.sub_patch(0.2..0.5, 0.9..0.4).is_err());
+        assert!(patch.sub_patch(0.3..0.3, 0.4..0.9).is_err());
+        assert!(patch.sub_patch(-0.1..0.5, 0.4..1.2).is_err());
+        assert!(patch.sub_patch(0.0..0.5, 0.4..1.0).is_ok());
+    }
+}
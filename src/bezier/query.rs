@@ -0,0 +1,230 @@
+use glam::{vec2, Mat2, Mat3, Vec3};
+
+use crate::bezier::patch::{BezierPatch, SurfaceDerivatives};
+use crate::bezier::surface::BezierSurface;
+
+/// Subdivision depth at which sub-patches are handed over to Newton iteration.
+const MAX_DEPTH: usize = 6;
+const NEWTON_ITERATIONS: usize = 16;
+/// Number of best grid samples used as starting points when projecting onto the surface.
+const PROJECTION_STARTS: usize = 4;
+
+impl BezierSurface {
+    /// First intersection of the ray `origin + t * dir` (`t ≥ 0`) with the surface, as
+    /// `(u, v, t, point, normal)`.
+    ///
+    /// Sub-patches whose bounding box the ray misses are discarded, the remaining ones are split
+    /// until small enough for Newton iteration to converge from their center. Trimmed-away parts
+    /// of the surface are ignored.
+    pub fn intersect_ray(&self, origin: Vec3, dir: Vec3) -> Option<(f32, f32, f32, Vec3, Vec3)> {
+        let patch = self.evaluated_patch();
+        let (min, max) = patch.bounding_box();
+        let tolerance = 1e-5 * (max - min).max_element().max(1.);
+        let (mut best, mut best_t) = (None, f32::INFINITY);
+        let mut stack = vec![(patch.clone(), [0., 1.], [0., 1.], 0)];
+        while let Some((sub, u_range, v_range, depth)) = stack.pop() {
+            let (min, max) = sub.bounding_box();
+            let (near, far) = match slab(origin, dir, min - tolerance, max + tolerance) {
+                Some(range) => range,
+                None => continue,
+            };
+            if far < 0. || near > best_t {
+                continue;
+            }
+            if depth < MAX_DEPTH {
+                let (u_mid, v_mid) = (mid(u_range), mid(v_range));
+                let (left, right) = sub.split_u(0.5);
+                for (half, u_range) in [(left, [u_range[0], u_mid]), (right, [u_mid, u_range[1]])] {
+                    let (bottom, top) = half.split_v(0.5);
+                    stack.push((bottom, u_range, [v_range[0], v_mid], depth + 1));
+                    stack.push((top, u_range, [v_mid, v_range[1]], depth + 1));
+                }
+                continue;
+            }
+            let hit = newton(patch, origin, dir, mid(u_range), mid(v_range), tolerance)
+                .filter(|&(_, _, t)| t >= 0. && t < best_t)
+                .filter(|&(u, v, _)| self.trim().iter().all(|trim| trim.contains(vec2(u, v))));
+            if let Some((_, _, t)) = hit {
+                best = hit;
+                best_t = t;
+            }
+        }
+        best.map(|(u, v, t)| (u, v, t, origin + t * dir, self.normal(u, v)))
+    }
+
+    /// Parameters of the surface point closest to `point`, and the distance between the two.
+    ///
+    /// The surface is sampled on a grid finer than its degree, and Newton iteration refines the
+    /// best samples; the trim is not taken into account.
+    pub fn project(&self, point: Vec3) -> (f32, f32, f32) {
+        let patch = self.evaluated_patch();
+        let (degree_u, degree_v) = patch.degree();
+        let (rows, columns) = (2 * degree_v + 2, 2 * degree_u + 2);
+        let mut samples = (0..=rows)
+            .flat_map(|j| {
+                (0..=columns).map(move |i| (i as f32 / columns as f32, j as f32 / rows as f32))
+            })
+            .map(|(u, v)| (u, v, patch.get_point(u, v).distance_squared(point)))
+            .collect::<Vec<_>>();
+        samples.sort_by(|a, b| a.2.total_cmp(&b.2));
+        samples
+            .into_iter()
+            .take(PROJECTION_STARTS)
+            .map(|(u, v, _)| closest(patch, point, u, v))
+            .map(|(u, v)| (u, v, patch.get_point(u, v).distance(point)))
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .unwrap()
+    }
+}
+
+/// Entry and exit parameters of the ray through the axis-aligned box, if it crosses it.
+fn slab(origin: Vec3, dir: Vec3, min: Vec3, max: Vec3) -> Option<(f32, f32)> {
+    let inv = dir.recip();
+    let (t0, t1) = ((min - origin) * inv, (max - origin) * inv);
+    // A zero direction component yields NaN when the origin lies on the slab boundary
+    let (near, far) = (t0.min(t1), t0.max(t1));
+    let near = [near.x, near.y, near.z]
+        .into_iter()
+        .filter(|t| !t.is_nan())
+        .fold(f32::NEG_INFINITY, f32::max);
+    let far = [far.x, far.y, far.z]
+        .into_iter()
+        .filter(|t| !t.is_nan())
+        .fold(f32::INFINITY, f32::min);
+    (near <= far).then_some((near, far))
+}
+
+/// Solves `S(u, v) = origin + t * dir` starting from `(u, v)` and the closest ray parameter.
+fn newton(
+    patch: &BezierPatch,
+    origin: Vec3,
+    dir: Vec3,
+    mut u: f32,
+    mut v: f32,
+    tolerance: f32,
+) -> Option<(f32, f32, f32)> {
+    let mut t = (patch.get_point(u, v) - origin).dot(dir) / dir.length_squared();
+    for _ in 0..NEWTON_ITERATIONS {
+        let SurfaceDerivatives { point, du, dv, .. } = patch.derivatives(u, v);
+        let residual = point - origin - t * dir;
+        if residual.length() < tolerance {
+            let inside = |x: f32| (-1e-4..=1. + 1e-4).contains(&x);
+            return (inside(u) && inside(v)).then(|| (u.clamp(0., 1.), v.clamp(0., 1.), t));
+        }
+        let jacobian = Mat3::from_cols(du, dv, -dir);
+        if jacobian.determinant().abs() < f32::EPSILON {
+            return None;
+        }
+        let step = jacobian.inverse() * residual;
+        // Keep iterating near the domain so that out-of-range steps can come back
+        u = (u - step.x).clamp(-0.5, 1.5);
+        v = (v - step.y).clamp(-0.5, 1.5);
+        t -= step.z;
+    }
+    None
+}
+
+/// Minimizes `|S(u, v) - point|²` over the domain starting from `(u, v)`.
+fn closest(patch: &BezierPatch, point: Vec3, mut u: f32, mut v: f32) -> (f32, f32) {
+    for _ in 0..NEWTON_ITERATIONS {
+        let SurfaceDerivatives {
+            point: p,
+            du,
+            dv,
+            duu,
+            duv,
+            dvv,
+        } = patch.derivatives(u, v);
+        let offset = p - point;
+        let gradient = vec2(offset.dot(du), offset.dot(dv));
+        let hessian = Mat2::from_cols(
+            vec2(du.dot(du) + offset.dot(duu), du.dot(dv) + offset.dot(duv)),
+            vec2(du.dot(dv) + offset.dot(duv), dv.dot(dv) + offset.dot(dvv)),
+        );
+        // Away from a minimum the Hessian can be indefinite; fall back to the Gauss-Newton one
+        let hessian = if hessian.determinant() > f32::EPSILON && hessian.x_axis.x > 0. {
+            hessian
+        } else {
+            Mat2::from_cols(vec2(du.dot(du), du.dot(dv)), vec2(du.dot(dv), dv.dot(dv)))
+        };
+        if hessian.determinant().abs() < f32::EPSILON {
+            break;
+        }
+        let step = hessian.inverse() * gradient;
+        let (next_u, next_v) = ((u - step.x).clamp(0., 1.), (v - step.y).clamp(0., 1.));
+        let moved = (next_u - u).abs().max((next_v - v).abs());
+        u = next_u;
+        v = next_v;
+        if moved < 1e-6 {
+            break;
+        }
+    }
+    (u, v)
+}
+
+fn mid([start, end]: [f32; 2]) -> f32 {
+    (start + end) / 2.
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Vec3};
+    use test_log::test;
+
+    use crate::bezier::patch::BezierPatch;
+    use crate::bezier::surface::BezierSurface;
+
+    #[test]
+    fn ray_hits_bump() {
+        let surface = BezierSurface::from(
+            BezierPatch::from_rows([
+                [vec3(0., 0., 0.), vec3(1., 0., 0.), vec3(2., 0., 0.)],
+                [vec3(0., 0., 1.), vec3(1., 2., 1.), vec3(2., 0., 1.)],
+                [vec3(0., 0., 2.), vec3(1., 0., 2.), vec3(2., 0., 2.)],
+            ])
+            .unwrap(),
+        );
+        let origin = vec3(1., 5., 1.);
+        let (u, v, t, point, normal) = surface.intersect_ray(origin, -Vec3::Y).unwrap();
+        assert!((u - 0.5).abs() < 1e-4 && (v - 0.5).abs() < 1e-4);
+        assert!((point - vec3(1., 0.5, 1.)).length() < 1e-4);
+        assert!((t - 4.5).abs() < 1e-4);
+        assert!((point - surface.get_point(u, v)).length() < 1e-4);
+        assert!(normal.dot(Vec3::Y).abs() > 1. - 1e-4);
+
+        // Slanted ray hitting off-center, and one passing beside the patch
+        let target = surface.get_point(0.2, 0.7);
+        let origin = vec3(3., 4., -1.);
+        let (u, v, ..) = surface.intersect_ray(origin, target - origin).unwrap();
+        assert!((u - 0.2).abs() < 1e-3 && (v - 0.7).abs() < 1e-3);
+        assert!(surface.intersect_ray(vec3(3., 5., 1.), -Vec3::Y).is_none());
+        assert!(surface.intersect_ray(origin, origin - target).is_none());
+    }
+
+    #[test]
+    fn projection_finds_closest_point() {
+        let surface = BezierSurface::from(
+            BezierPatch::from_rows([
+                [vec3(0., 0., 0.), vec3(1., 0., 0.), vec3(2., 0., 0.)],
+                [vec3(0., 0., 1.), vec3(1., 2., 1.), vec3(2., 0., 1.)],
+                [vec3(0., 0., 2.), vec3(1., 0., 2.), vec3(2., 0., 2.)],
+            ])
+            .unwrap(),
+        );
+        let (u, v, distance) = surface.project(vec3(1., 3., 1.));
+        assert!((u - 0.5).abs() < 1e-4 && (v - 0.5).abs() < 1e-4);
+        assert!((distance - 2.5).abs() < 1e-4);
+
+        for (u, v) in [(0.3, 0.8), (0.9, 0.15)] {
+            let point = surface.get_point(u, v) + 0.1 * surface.normal(u, v);
+            let (pu, pv, distance) = surface.project(point);
+            assert!((pu - u).abs() < 1e-3 && (pv - v).abs() < 1e-3);
+            assert!((distance - 0.1).abs() < 1e-4);
+        }
+
+        // Outside the domain, the closest point lies on the boundary
+        let (u, v, distance) = surface.project(vec3(-1., 0., 1.));
+        assert!(u.abs() < 1e-6 && (v - 0.5).abs() < 1e-4);
+        assert!((distance - 1.).abs() < 1e-4);
+    }
+}
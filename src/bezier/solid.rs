@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+
+use glam::{Vec2, Vec3};
+
+use crate::bezier::parametric::ParametricSurface;
+use crate::bezier::tessellation::TessellationBuilder;
+use crate::mesh::{MeshData, Vertex};
+
+impl TessellationBuilder {
+    /// Closed solid made of the tessellated surface, a copy of it offset by `thickness` along the
+    /// surface normals, and walls joining the borders of the two skins.
+    ///
+    /// The solid is watertight when vertices are identified by position: every edge is shared by
+    /// exactly two triangles wound in opposite directions, and all triangles face outward. Walls
+    /// are flat shaded, so their vertices are not shared with the skins.
+    pub fn build_solid<S: ParametricSurface + Sync + ?Sized>(
+        &self,
+        surface: &S,
+        thickness: f32,
+    ) -> anyhow::Result<MeshData> {
+        anyhow::ensure!(
+            thickness != 0. && thickness.is_finite(),
+            "Thickness must be finite and non-zero, got {}",
+            thickness
+        );
+        Ok(thicken(&self.build(surface)?, thickness))
+    }
+}
+
+fn thicken(skin: &MeshData, thickness: f32) -> MeshData {
+    // Vertices at the same position (seams, collapsed edges) move together so that the offset
+    // skin keeps the connectivity of the surface
+    let group = skin.position_groups();
+    let mut normals = vec![Vec3::ZERO; group.len()];
+    for (vertex, &g) in skin.vertices.iter().zip(&group) {
+        normals[g] += vertex.normal;
+    }
+
+    let n = skin.vertices.len() as u32;
+    let offset = skin.vertices.iter().zip(&group).map(|(vertex, &g)| Vertex {
+        position: vertex.position + thickness * normals[g].normalize_or_zero(),
+        ..*vertex
+    });
+    let mut vertices = skin.vertices.clone();
+    vertices.extend(offset);
+    // The skin behind the other one faces the opposite way
+    let back = if thickness > 0. { 0..n } else { n..2 * n };
+    for vertex in &mut vertices[back.start as usize..back.end as usize] {
+        vertex.normal = -vertex.normal;
+        vertex.tangent = -vertex.tangent;
+        vertex.curvature = Vec2::ZERO;
+    }
+
+    let triangles = skin
+        .indices
+        .chunks_exact(3)
+        .map(|t| [t[0], t[1], t[2]])
+        .collect::<Vec<_>>();
+    let mut indices = Vec::with_capacity(4 * skin.indices.len());
+    for &[a, b, c] in &triangles {
+        if back.start == 0 {
+            indices.extend([a, c, b, a + n, b + n, c + n]);
+        } else {
+            indices.extend([a, b, c, a + n, c + n, b + n]);
+        }
+    }
+
+    // Border edges are the ones whose reverse is not part of any triangle
+    let edges = triangles
+        .iter()
+        .flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)])
+        .map(|(a, b)| (group[a as usize], group[b as usize]))
+        .collect::<HashSet<_>>();
+    for &[a, b, c] in &triangles {
+        for (a, b) in [(a, b), (b, c), (c, a)] {
+            let (ga, gb) = (group[a as usize], group[b as usize]);
+            if ga == gb || edges.contains(&(gb, ga)) {
+                continue;
+            }
+            let (a0, b0) = (vertices[a as usize], vertices[b as usize]);
+            let (a1, b1) = (vertices[(a + n) as usize], vertices[(b + n) as usize]);
+            let along = (b0.position - a0.position).normalize_or_zero();
+            // The surface lies to the left of its border edges around the normal
+            let outward = along.cross(normals[ga] + normals[gb]).normalize_or_zero();
+            let wall = |corner: Vertex| Vertex {
+                normal: outward,
+                tangent: along,
+                curvature: Vec2::ZERO,
+                ..corner
+            };
+            let start = vertices.len() as u32;
+            vertices.extend([a0, b0, b1, a1].map(wall));
+            let [a0, b0, b1, a1] = [start, start + 1, start + 2, start + 3];
+            if thickness > 0. {
+                indices.extend([a0, b0, b1, a0, b1, a1]);
+            } else {
+                indices.extend([a0, a1, b1, a0, b1, b0]);
+            }
+        }
+    }
+
+    MeshData { vertices, indices }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use glam::{vec3, Vec3};
+    use test_log::test;
+
+    use crate::bezier::nurbs::NurbsSurface;
+    use crate::bezier::patch::BezierPatch;
+    use crate::bezier::surface::BezierSurface;
+    use crate::bezier::tessellation::TessellationBuilder;
+    use crate::mesh::MeshData;
+
+    /// Checks that every edge is matched by its reverse, and returns the enclosed volume.
+    fn closed_volume(mesh: &MeshData) -> f32 {
+        let key = |i: u32| {
+            mesh.vertices[i as usize]
+                .position
+                .to_array()
+                .map(f32::to_bits)
+        };
+        let mut edges = HashMap::<_, i32>::new();
+        let mut volume = 0.;
+        for t in mesh.indices.chunks_exact(3) {
+            for (a, b) in [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])] {
+                if key(a) != key(b) {
+                    *edges.entry((key(a), key(b))).or_default() += 1;
+                    *edges.entry((key(b), key(a))).or_default() -= 1;
+                }
+            }
+            let [a, b, c] = [t[0], t[1], t[2]].map(|i| mesh.vertices[i as usize].position);
+            volume += a.dot(b.cross(c)) / 6.;
+        }
+        assert!(edges.values().all(|&count| count == 0));
+        volume
+    }
+
+    #[test]
+    fn thickened_surfaces_are_closed() {
+        let plane = BezierSurface::from(
+            BezierPatch::from_rows([
+                [Vec3::ZERO, vec3(0., 0., 2.)],
+                [vec3(3., 0., 0.), vec3(3., 0., 2.)],
+            ])
+            .unwrap(),
+        );
+        let normal = plane.normal(0.5, 0.5);
+        for thickness in [0.5, -0.25] {
+            let solid = TessellationBuilder::new(4, 5)
+                .build_solid(&plane, thickness)
+                .unwrap();
+            assert!((closed_volume(&solid) - 6. * thickness.abs()).abs() < 1e-4);
+            // Both skins are there, on each side of the offset
+            let extent = solid
+                .vertices
+                .iter()
+                .map(|v| v.position.dot(normal))
+                .fold((0f32, 0f32), |(min, max), d| (min.min(d), max.max(d)));
+            assert!((extent.1 - extent.0 - thickness.abs()).abs() < 1e-5);
+        }
+
+        // A tube has no wall along its seam, only at its two ends
+        let tube = NurbsSurface::cylinder(Vec3::ZERO, 1., 1.);
+        let solid = TessellationBuilder::new(64, 4)
+            .build_solid(&tube, 0.1)
+            .unwrap();
+        let volume = closed_volume(&solid);
+        let expected = std::f32::consts::PI * (1.1f32.powi(2) - 1.);
+        assert!((volume - expected).abs() < 0.01 * expected);
+
+        assert!(TessellationBuilder::new(4, 4)
+            .build_solid(&plane, 0.)
+            .is_err());
+    }
+}
@@ -1,22 +1,54 @@
-use glam::{vec2, Vec3};
+use std::sync::OnceLock;
 
+use glam::Vec3;
+
+use crate::bezier::basis::BasisTable;
+use crate::bezier::curvature::Curvature;
 use crate::bezier::curve::BezierCurve;
-use crate::mesh::{Mesh, Vertex};
+use crate::bezier::integration;
+use crate::bezier::parametric::{nudged_normal, ParametricSurface};
+use crate::bezier::patch::{BezierPatch, SurfaceDerivatives};
+use crate::bezier::tessellation::TessellationBuilder;
+use crate::bezier::trim::Trim;
+use crate::mass::MassProperties;
+use crate::mesh::Mesh;
 
+#[derive(Debug, Clone)]
 pub struct BezierSurface {
-    profile: Vec<BezierCurve<Vec3>>,
+    patch: BezierPatch,
     dtprec: f32,
     looping: bool,
+    /// Patch closed along `v`, built on first use by looping surfaces.
+    closed: OnceLock<BezierPatch>,
+    trim: Option<Trim>,
 }
 
-impl BezierSurface {
-    pub fn new(profile: impl IntoIterator<Item = BezierCurve<Vec3>>) -> Self {
+impl From<BezierPatch> for BezierSurface {
+    fn from(patch: BezierPatch) -> Self {
         Self {
-            profile: profile.into_iter().collect(),
+            patch,
             dtprec: 1e-4,
             looping: false,
+            closed: OnceLock::new(),
+            trim: None,
         }
     }
+}
+
+impl BezierSurface {
+    pub fn new(profile: impl IntoIterator<Item = BezierCurve<Vec3>>) -> anyhow::Result<Self> {
+        let profile = profile.into_iter().collect::<Vec<_>>();
+        BezierPatch::from_profile(&profile).map(Self::from)
+    }
+
+    pub fn patch(&self) -> &BezierPatch {
+        &self.patch
+    }
+
+    pub fn patch_mut(&mut self) -> &mut BezierPatch {
+        self.closed.take();
+        &mut self.patch
+    }
 
     pub fn with_precision(mut self, prec: f32) -> Self {
         self.dtprec = prec;
@@ -28,50 +60,199 @@ impl BezierSurface {
         self
     }
 
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// Restricts the surface to part of its parameter domain.
+    pub fn with_trim(mut self, trim: Trim) -> Self {
+        self.trim = Some(trim);
+        self
+    }
+
+    pub fn trim(&self) -> Option<&Trim> {
+        self.trim.as_ref()
+    }
+
+    /// Control grid actually evaluated: the patch, closed along `v` if the surface is looping.
+    pub fn evaluated_patch(&self) -> &BezierPatch {
+        if self.looping {
+            self.closed.get_or_init(|| self.patch.closed_v())
+        } else {
+            &self.patch
+        }
+    }
+
+    pub fn is_closed_u(&self) -> bool {
+        self.evaluated_patch().is_closed_u(self.dtprec)
+    }
+
+    pub fn is_closed_v(&self) -> bool {
+        self.looping || self.patch.is_closed_v(self.dtprec)
+    }
+
     pub fn get_point(&self, u: f32, v: f32) -> Vec3 {
-        BezierCurve::new(self.profile.iter().map(|curve| curve.get_point(u)))
-            .looping(self.looping)
-            .get_point(v)
+        self.evaluated_patch().get_point(u, v)
+    }
+
+    pub fn derivatives(&self, u: f32, v: f32) -> SurfaceDerivatives {
+        self.evaluated_patch().derivatives(u, v)
+    }
+
+    pub fn partial_u(&self, u: f32, v: f32) -> Vec3 {
+        self.derivatives(u, v).du
+    }
+
+    pub fn partial_v(&self, u: f32, v: f32) -> Vec3 {
+        self.derivatives(u, v).dv
+    }
+
+    pub fn partial_uu(&self, u: f32, v: f32) -> Vec3 {
+        self.derivatives(u, v).duu
+    }
+
+    pub fn partial_uv(&self, u: f32, v: f32) -> Vec3 {
+        self.derivatives(u, v).duv
+    }
+
+    pub fn partial_vv(&self, u: f32, v: f32) -> Vec3 {
+        self.derivatives(u, v).dvv
     }
 
-    pub fn gradient(&self, u: f32, v: f32) -> Vec3 {
-        let anchor = self.get_point(u, v);
-        let du = (self.get_point(u + self.dtprec, v) - anchor) / self.dtprec;
-        let dv = (self.get_point(u, v + self.dtprec) - anchor) / self.dtprec;
-        du + dv
+    /// Unit normal `du × dv`.
+    ///
+    /// Where the surface is degenerate (collapsed edge or parallel partials), the normal is taken
+    /// slightly inside the domain, moving toward its center until the partials become independent.
+    pub fn normal(&self, u: f32, v: f32) -> Vec3 {
+        let patch = self.evaluated_patch();
+        nudged_normal(|u, v| patch.derivatives(u, v), u, v, self.dtprec)
+    }
+
+    /// Isoparametric curve along `v` at fixed `u`.
+    pub fn iso_u(&self, u: f32) -> BezierCurve<Vec3> {
+        self.evaluated_patch().iso_u(u)
+    }
+
+    /// Isoparametric curve along `u` at fixed `v`.
+    pub fn iso_v(&self, v: f32) -> BezierCurve<Vec3> {
+        self.evaluated_patch().iso_v(v)
+    }
+
+    /// Principal, Gaussian and mean curvatures at `(u, v)`.
+    pub fn curvature(&self, u: f32, v: f32) -> Curvature {
+        Curvature::new(&self.derivatives(u, v), self.normal(u, v))
+    }
+
+    /// Area, and volume properties if the surface is a closed shell.
+    pub fn mass_properties(&self) -> MassProperties {
+        integration::mass_properties(self)
     }
 
     pub fn triangulate(&self, u: usize, v: usize) -> anyhow::Result<Mesh> {
-        let mut vertices = Vec::with_capacity(u * v);
-        for j in (0..v).map(|k| (k as f32 + 1.) / v as f32) {
-            for i in (0..u).map(|k| (k as f32 + 1.) / u as f32) {
-                let position = self.get_point(i, j);
-                let normal = self.gradient(i, j).normalize();
-                let uv = vec2(i, j);
-                vertices.push(Vertex {
-                    position,
-                    normal,
-                    uv,
-                });
-            }
-        }
+        TessellationBuilder::new(u, v).build(self)?.upload()
+    }
+
+    /// Watertight solid of the surface thickened along its normals, see
+    /// [`TessellationBuilder::build_solid`].
+    pub fn thicken(&self, thickness: f32, u: usize, v: usize) -> anyhow::Result<Mesh> {
+        TessellationBuilder::new(u, v)
+            .build_solid(self, thickness)?
+            .upload()
+    }
+}
+
+impl ParametricSurface for BezierSurface {
+    fn derivatives(&self, u: f32, v: f32) -> SurfaceDerivatives {
+        self.derivatives(u, v)
+    }
+
+    fn grid_derivatives(&self, us: &[f32], vs: &[f32]) -> Vec<SurfaceDerivatives> {
+        let patch = self.evaluated_patch();
+        let (degree_u, degree_v) = patch.degree();
+        patch
+            .grid_derivatives(
+                &BasisTable::new(degree_u, us),
+                &BasisTable::new(degree_v, vs),
+            )
+            .expect("Basis tables are built from the patch degree")
+    }
+
+    fn get_point(&self, u: f32, v: f32) -> Vec3 {
+        self.get_point(u, v)
+    }
+
+    fn normal(&self, u: f32, v: f32) -> Vec3 {
+        self.normal(u, v)
+    }
+
+    fn degree(&self) -> (usize, usize) {
+        self.evaluated_patch().degree()
+    }
+
+    fn is_closed_u(&self) -> bool {
+        self.is_closed_u()
+    }
+
+    fn is_closed_v(&self) -> bool {
+        self.is_closed_v()
+    }
+
+    fn trim(&self) -> Option<&Trim> {
+        self.trim()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Vec3};
+    use test_log::test;
+
+    use super::BezierSurface;
+    use crate::bezier::curve::BezierCurve;
+    use crate::bezier::patch::BezierPatch;
+
+    #[test]
+    fn normals() {
+        let plane = BezierSurface::from(
+            BezierPatch::from_rows([[Vec3::ZERO, Vec3::X], [Vec3::Z, Vec3::X + Vec3::Z]]).unwrap(),
+        );
+        assert!((plane.normal(0.3, 0.8) + Vec3::Y).length() < 1e-6);
+
+        // The whole v = 0 edge collapses onto the origin
+        let cone = BezierSurface::from(
+            BezierPatch::from_rows([
+                [Vec3::ZERO, Vec3::ZERO, Vec3::ZERO],
+                [vec3(-1., 1., 0.), vec3(0., 1., 1.), vec3(1., 1., 0.)],
+            ])
+            .unwrap(),
+        );
+        let normal = cone.normal(0.5, 0.);
+        assert!(normal.is_finite());
+        assert!((normal.length() - 1.).abs() < 1e-5);
+        assert!(normal.dot(cone.normal(0.5, 0.5)) > 0.9);
+    }
 
-        let mut indices = Vec::with_capacity((u - 1) * (v - 1));
-        for j in 0..v - 1 {
-            for i in 0..u - 1 {
-                let idx = j * u + i;
-                let idx_next = idx + u;
-                indices.extend([
-                    /* face 1 */ idx,
-                    idx + 1,
-                    idx_next,
-                    /* face 2 */ idx + 1,
-                    idx_next + 1,
-                    idx_next,
-                ]);
-            }
+    #[test]
+    fn looping_closes_profile() {
+        let mut surface = BezierSurface::new([
+            BezierCurve::new([vec3(1., 0., 0.), vec3(1., 1., 0.)]),
+            BezierCurve::new([vec3(0., 0., 1.), vec3(0., 1., 1.)]),
+            BezierCurve::new([vec3(-1., 0., 0.), vec3(-1., 1., 0.)]),
+            BezierCurve::new([vec3(0., 0., -1.), vec3(0., 1., -1.)]),
+        ])
+        .unwrap()
+        .looping(true);
+        assert!(surface.is_closed_v() && !surface.is_closed_u());
+        for u in [0., 0.5, 1.] {
+            let seam = surface.get_point(u, 1.) - surface.get_point(u, 0.);
+            assert!(seam.length() < 1e-6);
+            assert!((surface.normal(u, 1.) - surface.normal(u, 0.)).length() < 1e-4);
         }
 
-        Mesh::new(vertices, indices.into_iter().map(|i| i as u32))
+        // Editing the patch also moves the control points closing it
+        let corner = vec3(1., -0.5, 0.);
+        *surface.patch_mut().get_mut(0, 0) = corner;
+        assert_eq!(surface.get_point(0., 0.), corner);
+        assert_eq!(surface.get_point(0., 1.), corner);
     }
 }
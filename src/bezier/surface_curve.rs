@@ -0,0 +1,191 @@
+use glam::{Vec2, Vec3};
+
+use crate::bezier::basis::GAUSS_LEGENDRE;
+use crate::bezier::curve::BezierCurve;
+use crate::bezier::parametric::ParametricSurface;
+use crate::mesh::{MeshData, Vertex};
+
+/// Segments the curve is first cut into when flattening or integrating, so that features smaller
+/// than a segment are not skipped over.
+const MIN_SEGMENTS: usize = 8;
+const MAX_DEPTH: u32 = 12;
+
+/// Curve drawn on a surface, given by a curve in its `(u, v)` parameter domain.
+#[derive(Debug, Clone)]
+pub struct SurfaceCurve<'a, S: ?Sized> {
+    surface: &'a S,
+    curve: BezierCurve<Vec2>,
+    derivative: BezierCurve<Vec2>,
+}
+
+impl<'a, S: ParametricSurface + ?Sized> SurfaceCurve<'a, S> {
+    pub fn new(surface: &'a S, curve: BezierCurve<Vec2>) -> Self {
+        let curve = curve.closed();
+        Self {
+            surface,
+            derivative: curve.derivative(),
+            curve,
+        }
+    }
+
+    pub fn surface(&self) -> &'a S {
+        self.surface
+    }
+
+    /// Curve in the parameter domain of the surface, closed if it was looping.
+    pub fn curve(&self) -> &BezierCurve<Vec2> {
+        &self.curve
+    }
+
+    pub fn get_point(&self, t: f32) -> Vec3 {
+        let uv = self.curve.get_point(t);
+        self.surface.get_point(uv.x, uv.y)
+    }
+
+    /// Derivative of the 3D curve with respect to `t`.
+    pub fn tangent(&self, t: f32) -> Vec3 {
+        let uv = self.curve.get_point(t);
+        let duv = self.derivative_at(t);
+        let derivatives = self.surface.derivatives(uv.x, uv.y);
+        derivatives.du * duv.x + derivatives.dv * duv.y
+    }
+
+    /// Arc length of the 3D curve.
+    pub fn length(&self) -> f32 {
+        let segments = MIN_SEGMENTS * self.curve.control_points().len();
+        let h = 1. / segments as f32;
+        (0..segments)
+            .map(|i| {
+                let center = (i as f32 + 0.5) * h;
+                GAUSS_LEGENDRE
+                    .iter()
+                    .map(|&(x, w)| w * self.tangent(center + x * h / 2.).length())
+                    .sum::<f32>()
+                    * h
+                    / 2.
+            })
+            .sum()
+    }
+
+    /// Curve parameters of a polyline staying within `tolerance` of the 3D curve.
+    pub fn flatten(&self, tolerance: f32) -> Vec<f32> {
+        let mut params = vec![0.];
+        for i in 0..MIN_SEGMENTS {
+            let (t0, t1) = (
+                i as f32 / MIN_SEGMENTS as f32,
+                (i + 1) as f32 / MIN_SEGMENTS as f32,
+            );
+            self.subdivide(&mut params, t0, t1, tolerance, 0);
+        }
+        params
+    }
+
+    /// Points of the 3D curve flattened to within `tolerance`.
+    pub fn polyline(&self, tolerance: f32) -> Vec<Vec3> {
+        self.flatten(tolerance)
+            .into_iter()
+            .map(|t| self.get_point(t))
+            .collect()
+    }
+
+    /// Line list following the flattened curve, to be drawn with
+    /// [`Mesh::wireframe`](crate::mesh::Mesh::wireframe).
+    ///
+    /// Vertices carry the surface normal and the `(u, v)` parameters of the surface as texture
+    /// coordinates.
+    pub fn lines(&self, tolerance: f32) -> MeshData {
+        let vertices = self
+            .flatten(tolerance)
+            .into_iter()
+            .map(|t| {
+                let uv = self.curve.get_point(t);
+                Vertex {
+                    position: self.surface.get_point(uv.x, uv.y),
+                    normal: self.surface.normal(uv.x, uv.y),
+                    uv,
+                    tangent: self.tangent(t).normalize_or_zero(),
+                    curvature: Vec2::ZERO,
+                }
+            })
+            .collect::<Vec<_>>();
+        let indices = (1..vertices.len() as u32)
+            .flat_map(|i| [i - 1, i])
+            .collect();
+        MeshData { vertices, indices }
+    }
+
+    fn derivative_at(&self, t: f32) -> Vec2 {
+        if self.derivative.is_empty() {
+            Vec2::ZERO
+        } else {
+            self.derivative.get_point(t)
+        }
+    }
+
+    /// Pushes the parameters after `t0` up to `t1`, splitting while the middle of the curve
+    /// strays from the chord.
+    fn subdivide(&self, params: &mut Vec<f32>, t0: f32, t1: f32, tolerance: f32, depth: u32) {
+        let mid = (t0 + t1) / 2.;
+        let (a, b) = (self.get_point(t0), self.get_point(t1));
+        let deviation = [0.25, 0.5, 0.75]
+            .into_iter()
+            .map(|s| {
+                let point = self.get_point(t0 + s * (t1 - t0));
+                let chord = a.lerp(b, s);
+                point.distance(chord)
+            })
+            .fold(0f32, f32::max);
+        if deviation > tolerance && depth < MAX_DEPTH {
+            self.subdivide(params, t0, mid, tolerance, depth + 1);
+            self.subdivide(params, mid, t1, tolerance, depth + 1);
+        } else {
+            params.push(t1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::TAU;
+
+    use glam::{vec2, vec3, Vec3};
+    use test_log::test;
+
+    use super::SurfaceCurve;
+    use crate::bezier::curve::BezierCurve;
+    use crate::bezier::nurbs::NurbsSurface;
+    use crate::bezier::patch::BezierPatch;
+    use crate::bezier::surface::BezierSurface;
+
+    #[test]
+    fn maps_domain_curves() {
+        let plane = BezierSurface::from(
+            BezierPatch::from_rows([
+                [Vec3::ZERO, vec3(2., 0., 0.)],
+                [vec3(0., 0., 3.), vec3(2., 0., 3.)],
+            ])
+            .unwrap(),
+        );
+        let diagonal = SurfaceCurve::new(&plane, BezierCurve::new([vec2(0., 0.), vec2(1., 1.)]));
+        assert!((diagonal.length() - 13f32.sqrt()).abs() < 1e-4);
+        assert!((diagonal.get_point(0.5) - vec3(1., 0., 1.5)).length() < 1e-5);
+        assert_eq!(diagonal.flatten(1e-3).len(), 9);
+
+        // The `v = 1/2` isoline of a cylinder is a full circle
+        let cylinder = NurbsSurface::cylinder(Vec3::ZERO, 2., 1.);
+        let circle = SurfaceCurve::new(&cylinder, BezierCurve::new([vec2(0., 0.5), vec2(1., 0.5)]));
+        assert!((circle.length() - 2. * TAU).abs() < 1e-3);
+        let tolerance = 1e-3;
+        let params = circle.flatten(tolerance);
+        for pair in params.windows(2) {
+            let (a, b) = (circle.get_point(pair[0]), circle.get_point(pair[1]));
+            let middle = circle.get_point((pair[0] + pair[1]) / 2.);
+            assert!(middle.distance(a.lerp(b, 0.5)) <= tolerance);
+        }
+        let lines = circle.lines(tolerance);
+        assert_eq!(lines.indices.len(), 2 * (params.len() - 1));
+        assert!(
+            (lines.vertices[0].position - lines.vertices.last().unwrap().position).length() < 1e-4
+        );
+    }
+}
@@ -0,0 +1,985 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+use glam::{vec2, Vec2};
+use rayon::prelude::*;
+
+use crate::bezier::curvature::Curvature;
+use crate::bezier::displacement::Displacement;
+use crate::bezier::network::Edge;
+use crate::bezier::parametric::ParametricSurface;
+use crate::bezier::patch::SurfaceDerivatives;
+use crate::bezier::trim::Trim;
+use crate::mesh::{MeshData, Vertex};
+
+/// Relative length of `du × dv` under which the normal is left to [`ParametricSurface::normal`],
+/// above the thresholds surfaces use to detect degenerate points.
+const NORMAL_PRECISION: f32 = 1e-3;
+
+/// How the parameter domain is sampled during tessellation.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Sampling {
+    /// Regular grid with `u` × `v` vertices.
+    Uniform { u: usize, v: usize },
+    /// Quadtree refinement of the domain, splitting cells whose chordal error exceeds
+    /// `tolerance` (in world units) or whose corner normals deviate by more than `max_angle`
+    /// (in radians), down to cells of size `2^-max_depth`.
+    Adaptive {
+        tolerance: f32,
+        max_angle: f32,
+        max_depth: u32,
+    },
+}
+
+/// Tessellation settings for [`ParametricSurface`]s.
+///
+/// Samples the closed `[0, 1]²` parameter domain, so the border of the surface is part of the
+/// mesh. Triangles are wound counter-clockwise around the surface normal, as expected when
+/// culling back faces; [`Self::flipped`] reverses both the winding and the normals.
+#[derive(Debug, Clone)]
+pub struct TessellationBuilder {
+    sampling: Sampling,
+    uv_scale: Vec2,
+    uv_offset: Vec2,
+    flipped: bool,
+    curvature: bool,
+    displacement: Option<Displacement>,
+}
+
+impl Default for TessellationBuilder {
+    fn default() -> Self {
+        Self {
+            sampling: Sampling::Uniform { u: 32, v: 32 },
+            uv_scale: Vec2::ONE,
+            uv_offset: Vec2::ZERO,
+            flipped: false,
+            curvature: false,
+            displacement: None,
+        }
+    }
+}
+
+impl TessellationBuilder {
+    pub fn new(u: usize, v: usize) -> Self {
+        Self::default().with_resolution(u, v)
+    }
+
+    /// Number of vertices along each parameter direction.
+    pub fn with_resolution(self, u: usize, v: usize) -> Self {
+        self.with_sampling(Sampling::Uniform { u, v })
+    }
+
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    pub fn with_uv_scale(mut self, scale: Vec2) -> Self {
+        self.uv_scale = scale;
+        self
+    }
+
+    pub fn with_uv_offset(mut self, offset: Vec2) -> Self {
+        self.uv_offset = offset;
+        self
+    }
+
+    pub fn flipped(mut self, v: bool) -> Self {
+        self.flipped = v;
+        self
+    }
+
+    /// Bakes the Gaussian and mean curvature of the surface into [`Vertex::curvature`].
+    pub fn with_curvature(mut self, v: bool) -> Self {
+        self.curvature = v;
+        self
+    }
+
+    /// Moves the vertices along their normal by the height field, see [`Displacement`].
+    pub fn with_displacement(mut self, displacement: Displacement) -> Self {
+        self.displacement = Some(displacement);
+        self
+    }
+
+    /// Tessellates the surface on the rayon thread pool, for the mesh data to be uploaded later
+    /// from the thread owning the OpenGL context.
+    ///
+    /// A panic during tessellation is caught and returned as an error by the job.
+    pub fn spawn<S: ParametricSurface + Send + Sync + 'static>(
+        &self,
+        surface: S,
+    ) -> TessellationJob {
+        let job = TessellationJob {
+            slot: Arc::new((Mutex::new(JobState::Running), Condvar::new())),
+        };
+        let builder = self.clone();
+        let slot = job.slot.clone();
+        rayon::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| builder.build(&surface)))
+                .unwrap_or_else(|payload| {
+                    let message = payload
+                        .downcast_ref::<&str>()
+                        .copied()
+                        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                        .unwrap_or("unknown cause");
+                    Err(anyhow::anyhow!("Tessellation panicked: {}", message))
+                });
+            let (state, done) = &*slot;
+            *state.lock().unwrap_or_else(PoisonError::into_inner) = JobState::Done(result);
+            done.notify_all();
+        });
+        job
+    }
+
+    pub fn build<S: ParametricSurface + Sync + ?Sized>(
+        &self,
+        surface: &S,
+    ) -> anyhow::Result<MeshData> {
+        let mut mesh = match self.sampling {
+            Sampling::Uniform { u, v } => self.build_uniform(surface, u, v),
+            Sampling::Adaptive {
+                tolerance,
+                max_angle,
+                max_depth,
+            } => self.build_adaptive(surface, tolerance, max_angle, max_depth),
+        }?;
+        if let Some(displacement) = &self.displacement {
+            displacement.apply(&mut mesh, self.flipped);
+        }
+        Ok(mesh)
+    }
+
+    fn build_uniform<S: ParametricSurface + Sync + ?Sized>(
+        &self,
+        surface: &S,
+        u: usize,
+        v: usize,
+    ) -> anyhow::Result<MeshData> {
+        anyhow::ensure!(
+            u >= 2 && v >= 2,
+            "Tessellation needs at least 2x2 vertices, got {}x{}",
+            u,
+            v
+        );
+        let param = |i: usize, j: usize| vec2(i as f32 / (u - 1) as f32, j as f32 / (v - 1) as f32);
+        if let Some(trim) = surface.trim() {
+            let cells = (0..v - 1)
+                .flat_map(|j| (0..u - 1).map(move |i| (i, j)))
+                .map(|(i, j)| {
+                    vec![
+                        param(i, j),
+                        param(i + 1, j),
+                        param(i + 1, j + 1),
+                        param(i, j + 1),
+                    ]
+                });
+            return Ok(self.build_trimmed(surface, trim, cells));
+        }
+
+        let us = (0..u)
+            .map(|i| i as f32 / (u - 1) as f32)
+            .collect::<Vec<_>>();
+        let vs = (0..v)
+            .map(|j| j as f32 / (v - 1) as f32)
+            .collect::<Vec<_>>();
+        let mut vertices = surface
+            .grid_derivatives(&us, &vs)
+            .into_par_iter()
+            .enumerate()
+            .map(|(k, derivatives)| self.vertex_from(surface, param(k % u, k / u), derivatives))
+            .collect::<Vec<_>>();
+
+        if surface.is_closed_v() {
+            for i in 0..u {
+                weld(&mut vertices, i, (v - 1) * u + i);
+            }
+        }
+        if surface.is_closed_u() {
+            for j in 0..v {
+                weld(&mut vertices, j * u, j * u + u - 1);
+            }
+        }
+
+        let mut indices = Vec::with_capacity((u - 1) * (v - 1) * 6);
+        for j in 0..v - 1 {
+            for i in 0..u - 1 {
+                let idx = j * u + i;
+                let idx_next = idx + u;
+                self.push_quad(&mut indices, [idx, idx + 1, idx_next + 1, idx_next]);
+            }
+        }
+
+        Ok(MeshData {
+            vertices,
+            indices: indices.into_iter().map(|i| i as u32).collect(),
+        })
+    }
+
+    fn build_adaptive<S: ParametricSurface + Sync + ?Sized>(
+        &self,
+        surface: &S,
+        tolerance: f32,
+        max_angle: f32,
+        max_depth: u32,
+    ) -> anyhow::Result<MeshData> {
+        let grid = self.refine(surface, tolerance, max_angle, max_depth)?;
+        Ok(self.triangulate(surface, &grid).0)
+    }
+
+    /// Quadtree refinement of the surface with the adaptive sampling of the builder, to be
+    /// triangulated with [`Self::build_grid`].
+    pub(crate) fn adaptive_grid<S: ParametricSurface + Sync + ?Sized>(
+        &self,
+        surface: &S,
+    ) -> anyhow::Result<AdaptiveGrid> {
+        match self.sampling {
+            Sampling::Adaptive {
+                tolerance,
+                max_angle,
+                max_depth,
+            } => self.refine(surface, tolerance, max_angle, max_depth),
+            Sampling::Uniform { .. } => anyhow::bail!("Tessellation sampling is not adaptive"),
+        }
+    }
+
+    /// Triangulates a grid from [`Self::adaptive_grid`], returning the mesh and the indices of
+    /// its vertices along each edge of the domain, in the order of [`Edge::ALL`].
+    pub(crate) fn build_grid<S: ParametricSurface + Sync + ?Sized>(
+        &self,
+        surface: &S,
+        grid: &AdaptiveGrid,
+    ) -> (MeshData, [Vec<usize>; 4]) {
+        let (mut mesh, edges) = self.triangulate(surface, grid);
+        if let Some(displacement) = &self.displacement {
+            displacement.apply(&mut mesh, self.flipped);
+        }
+        (mesh, edges)
+    }
+
+    fn refine<S: ParametricSurface + Sync + ?Sized>(
+        &self,
+        surface: &S,
+        tolerance: f32,
+        max_angle: f32,
+        max_depth: u32,
+    ) -> anyhow::Result<AdaptiveGrid> {
+        anyhow::ensure!(
+            max_depth < 16,
+            "Maximum tessellation depth is 15, got {}",
+            max_depth
+        );
+        let (deg_u, deg_v) = surface.degree();
+        let min_depth = (deg_u.max(deg_v).max(1) as f32).log2().ceil() as u32;
+        let refiner = Refiner {
+            surface,
+            tolerance,
+            min_cos: max_angle.cos(),
+            min_depth: min_depth.min(max_depth),
+            max_depth,
+        };
+        let leaves = refiner.refine(Cell::root(max_depth), 0);
+
+        let mut grid = AdaptiveGrid {
+            leaves,
+            size: 1 << max_depth,
+            rows: BTreeSet::new(),
+            columns: BTreeSet::new(),
+            closed_u: surface.is_closed_u(),
+            closed_v: surface.is_closed_v(),
+        };
+        for cell in &grid.leaves {
+            for (x, y) in cell.corners() {
+                grid.rows.insert((y, x));
+                grid.columns.insert((x, y));
+            }
+        }
+        // Closed surfaces need the same vertices on both sides of the seam
+        for edge in Edge::ALL {
+            let samples = grid.edge(edge);
+            grid.insert_edge(edge, samples);
+        }
+        Ok(grid)
+    }
+
+    /// Triangulates the leaves of the grid, fanning the cells with vertices inside their edges,
+    /// and returns the indices of the vertices along each edge of the domain.
+    fn triangulate<S: ParametricSurface + Sync + ?Sized>(
+        &self,
+        surface: &S,
+        grid: &AdaptiveGrid,
+    ) -> (MeshData, [Vec<usize>; 4]) {
+        let AdaptiveGrid {
+            rows,
+            columns,
+            closed_u,
+            closed_v,
+            size: n,
+            ..
+        } = grid;
+        let (closed_u, closed_v, n) = (*closed_u, *closed_v, *n);
+        let scale = 1. / (2 * n) as f32;
+        let mut params = Vec::new();
+        let mut lookup = HashMap::new();
+        let mut vertex = |(x, y): (u32, u32)| {
+            *lookup.entry((x, y)).or_insert_with(|| {
+                params.push(vec2(x as f32, y as f32) * scale);
+                params.len() - 1
+            })
+        };
+        let mut indices = Vec::new();
+        let mut windows = Vec::new();
+        for cell in &grid.leaves {
+            // Boundary of the cell counter-clockwise, including the corners of finer neighbors
+            let Cell { x, y, size } = *cell;
+            let mut boundary = vec![(x, y)];
+            boundary.extend(rows.range((y, x + 1)..(y, x + size)).map(|&(y, x)| (x, y)));
+            boundary.push((x + size, y));
+            boundary.extend(columns.range((x + size, y + 1)..(x + size, y + size)));
+            boundary.push((x + size, y + size));
+            boundary.extend(
+                rows.range((y + size, x + 1)..(y + size, x + size))
+                    .rev()
+                    .map(|&(y, x)| (x, y)),
+            );
+            boundary.push((x, y + size));
+            boundary.extend(columns.range((x, y + 1)..(x, y + size)).rev());
+            if surface.trim().is_some() {
+                let window = boundary
+                    .iter()
+                    .map(|&(x, y)| vec2(x as f32, y as f32) * 2. * scale);
+                windows.push(window.collect());
+                continue;
+            }
+
+            let boundary = boundary
+                .into_iter()
+                .map(|(x, y)| vertex((2 * x, 2 * y)))
+                .collect::<Vec<_>>();
+            if let [a, b, c, d] = boundary[..] {
+                self.push_quad(&mut indices, [a, b, c, d]);
+            } else {
+                let center = vertex((2 * x + size, 2 * y + size));
+                for (k, &a) in boundary.iter().enumerate() {
+                    let b = boundary[(k + 1) % boundary.len()];
+                    self.push_triangle(&mut indices, [center, a, b]);
+                }
+            }
+        }
+
+        if let Some(trim) = surface.trim() {
+            // Trimmed edges are cut through, they have no vertices of their own to share
+            let mesh = self.build_trimmed(surface, trim, windows);
+            return (mesh, Default::default());
+        }
+
+        let mut vertices = self.vertices(surface, &params);
+        let seams = lookup
+            .iter()
+            .filter_map(|(&(x, y), &to)| {
+                let wrap = |closed: bool, k: u32| if closed && k == 2 * n { 0 } else { k };
+                let from = (wrap(closed_u, x), wrap(closed_v, y));
+                (from != (x, y)).then(|| (lookup[&from], to))
+            })
+            .collect::<Vec<_>>();
+        for (from, to) in seams {
+            weld(&mut vertices, from, to);
+        }
+
+        let edges = Edge::ALL.map(|edge| {
+            grid.edge(edge)
+                .into_iter()
+                .map(|k| lookup[&grid.position(edge, k)])
+                .collect()
+        });
+        let mesh = MeshData {
+            vertices,
+            indices: indices.into_iter().map(|i| i as u32).collect(),
+        };
+        (mesh, edges)
+    }
+
+    /// Triangulates the parts of the cells kept by the trimming. Cells are given by their
+    /// boundary in the parameter domain, counter-clockwise.
+    fn build_trimmed<S: ParametricSurface + Sync + ?Sized>(
+        &self,
+        surface: &S,
+        trim: &Trim,
+        cells: impl IntoIterator<Item = Vec<Vec2>>,
+    ) -> MeshData {
+        let region = trim.region();
+        let mut params = Vec::new();
+        let mut lookup = HashMap::new();
+        let mut indices = Vec::new();
+        for window in cells {
+            for triangle in region.clip(&window) {
+                let triangle = triangle.map(|param| {
+                    *lookup
+                        .entry(param.to_array().map(f32::to_bits))
+                        .or_insert_with(|| {
+                            params.push(param);
+                            params.len() - 1
+                        })
+                });
+                self.push_triangle(&mut indices, triangle);
+            }
+        }
+        let mut vertices = self.vertices(surface, &params);
+
+        let (closed_u, closed_v) = (surface.is_closed_u(), surface.is_closed_v());
+        let seams = lookup
+            .iter()
+            .filter_map(|(&[u, v], &to)| {
+                let wrap = |closed: bool, k: u32| {
+                    if closed && k == 1f32.to_bits() {
+                        0f32.to_bits()
+                    } else {
+                        k
+                    }
+                };
+                let from = [wrap(closed_u, u), wrap(closed_v, v)];
+                (from != [u, v])
+                    .then(|| lookup.get(&from).map(|&from| (from, to)))
+                    .flatten()
+            })
+            .collect::<Vec<_>>();
+        for (from, to) in seams {
+            weld(&mut vertices, from, to);
+        }
+
+        MeshData {
+            vertices,
+            indices: indices.into_iter().map(|i| i as u32).collect(),
+        }
+    }
+
+    /// Evaluates the vertices at the given parameters over the threads of the rayon pool.
+    fn vertices<S: ParametricSurface + Sync + ?Sized>(
+        &self,
+        surface: &S,
+        params: &[Vec2],
+    ) -> Vec<Vertex> {
+        params
+            .par_iter()
+            .map(|&param| self.vertex(surface, param))
+            .collect()
+    }
+
+    fn vertex<S: ParametricSurface + ?Sized>(&self, surface: &S, param: Vec2) -> Vertex {
+        self.vertex_from(surface, param, surface.derivatives(param.x, param.y))
+    }
+
+    /// Vertex at `param` from the derivatives already evaluated there.
+    fn vertex_from<S: ParametricSurface + ?Sized>(
+        &self,
+        surface: &S,
+        param: Vec2,
+        derivatives: SurfaceDerivatives,
+    ) -> Vertex {
+        // Only degenerate points need the surface to look around for a normal
+        let cross = derivatives.du.cross(derivatives.dv);
+        let normal = if cross.length()
+            > NORMAL_PRECISION * derivatives.du.length() * derivatives.dv.length()
+        {
+            cross.normalize()
+        } else {
+            surface.normal(param.x, param.y)
+        };
+        // Falls back to the direction of `du` rebuilt from `dv` when `du` vanishes
+        let tangent = derivatives
+            .du
+            .reject_from_normalized(normal)
+            .try_normalize()
+            .unwrap_or_else(|| derivatives.dv.cross(normal).normalize_or_zero());
+        let curvature = if self.curvature {
+            let curvature = Curvature::new(&derivatives, normal);
+            let curvature = if self.flipped {
+                curvature.flipped()
+            } else {
+                curvature
+            };
+            curvature.attribute()
+        } else {
+            Vec2::ZERO
+        };
+        Vertex {
+            position: derivatives.point,
+            normal: if self.flipped { -normal } else { normal },
+            uv: param * self.uv_scale + self.uv_offset,
+            tangent: tangent * self.uv_scale.x.signum(),
+            curvature,
+        }
+    }
+
+    /// Pushes the two triangles of a quad given counter-clockwise around `du × dv`.
+    fn push_quad(&self, indices: &mut Vec<usize>, [a, b, c, d]: [usize; 4]) {
+        self.push_triangle(indices, [a, b, d]);
+        self.push_triangle(indices, [b, c, d]);
+    }
+
+    /// Pushes a triangle given counter-clockwise around `du × dv`.
+    fn push_triangle(&self, indices: &mut Vec<usize>, [a, b, c]: [usize; 3]) {
+        if self.flipped {
+            indices.extend([a, c, b]);
+        } else {
+            indices.extend([a, b, c]);
+        }
+    }
+}
+
+/// Tessellation running in the background, see [`TessellationBuilder::spawn`].
+///
+/// Clones share the same result, which only one of them can take.
+#[derive(Clone)]
+pub struct TessellationJob {
+    slot: Arc<(Mutex<JobState>, Condvar)>,
+}
+
+enum JobState {
+    Running,
+    Done(anyhow::Result<MeshData>),
+    Taken,
+}
+
+impl JobState {
+    /// Takes the result out of a finished job, leaving it taken.
+    fn take(&mut self) -> anyhow::Result<MeshData> {
+        match std::mem::replace(self, JobState::Taken) {
+            JobState::Done(result) => result,
+            JobState::Taken => Err(anyhow::anyhow!("Tessellation result was already taken")),
+            JobState::Running => unreachable!("Tessellation job is still running"),
+        }
+    }
+}
+
+impl fmt::Debug for TessellationJob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TessellationJob")
+            .field("done", &self.is_done())
+            .finish()
+    }
+}
+
+impl TessellationJob {
+    fn state(&self) -> MutexGuard<'_, JobState> {
+        self.slot.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Whether the result is ready and not taken yet.
+    pub fn is_done(&self) -> bool {
+        matches!(*self.state(), JobState::Done(_))
+    }
+
+    /// Takes the result if the tessellation is done, without blocking. Returns an error once the
+    /// result has been taken.
+    pub fn try_take(&self) -> Option<anyhow::Result<MeshData>> {
+        let mut state = self.state();
+        match *state {
+            JobState::Running => None,
+            _ => Some(state.take()),
+        }
+    }
+
+    /// Blocks until the tessellation is done and takes the result. Returns an error if the result
+    /// has already been taken.
+    pub fn wait(self) -> anyhow::Result<MeshData> {
+        let done = &self.slot.1;
+        let mut state = done
+            .wait_while(self.state(), |state| matches!(state, JobState::Running))
+            .unwrap_or_else(PoisonError::into_inner);
+        state.take()
+    }
+}
+
+/// Gives vertex `to` the geometry of vertex `from`, keeping its texture coordinates.
+fn weld(vertices: &mut [Vertex], from: usize, to: usize) {
+    let Vertex {
+        position,
+        normal,
+        tangent,
+        curvature,
+        ..
+    } = vertices[from];
+    vertices[to] = Vertex {
+        position,
+        normal,
+        tangent,
+        curvature,
+        ..vertices[to]
+    };
+}
+
+/// Square cell of the adaptive quadtree, in units of the finest cell size.
+#[derive(Debug, Copy, Clone)]
+struct Cell {
+    x: u32,
+    y: u32,
+    size: u32,
+}
+
+impl Cell {
+    fn root(max_depth: u32) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            size: 1 << max_depth,
+        }
+    }
+
+    fn corners(&self) -> [(u32, u32); 4] {
+        let Self { x, y, size } = *self;
+        [(x, y), (x + size, y), (x + size, y + size), (x, y + size)]
+    }
+
+    fn children(&self) -> [Self; 4] {
+        let size = self.size / 2;
+        [(0, 0), (size, 0), (0, size), (size, size)].map(|(dx, dy)| Self {
+            x: self.x + dx,
+            y: self.y + dy,
+            size,
+        })
+    }
+}
+
+/// Leaves of the quadtree refinement of an adaptive tessellation, before triangulation.
+///
+/// Vertices are placed on a grid of `2^max_depth` cells along each direction, at the corners of
+/// the leaves, and along the edges of the domain where other tessellations need to meet them.
+#[derive(Debug, Clone)]
+pub(crate) struct AdaptiveGrid {
+    leaves: Vec<Cell>,
+    /// Number of cells of the grid along each direction.
+    size: u32,
+    /// Vertices by row then column, and by column then row.
+    rows: BTreeSet<(u32, u32)>,
+    columns: BTreeSet<(u32, u32)>,
+    closed_u: bool,
+    closed_v: bool,
+}
+
+impl AdaptiveGrid {
+    /// Number of cells of the grid along each direction.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Positions on the grid of the vertices along `edge`, in the order of the edge parameter.
+    pub fn edge(&self, edge: Edge) -> Vec<u32> {
+        let n = self.size;
+        match edge {
+            Edge::Bottom => self.rows.range((0, 0)..=(0, n)).map(|&(_, x)| x).collect(),
+            Edge::Top => self.rows.range((n, 0)..=(n, n)).map(|&(_, x)| x).collect(),
+            Edge::Left => self
+                .columns
+                .range((0, 0)..=(0, n))
+                .map(|&(_, y)| y)
+                .collect(),
+            Edge::Right => self
+                .columns
+                .range((n, 0)..=(n, n))
+                .map(|&(_, y)| y)
+                .collect(),
+        }
+    }
+
+    /// Adds vertices along `edge`, and along the opposite edge if the surface is closed across
+    /// them. Returns whether any vertex was new.
+    pub fn insert_edge(&mut self, edge: Edge, samples: impl IntoIterator<Item = u32>) -> bool {
+        let opposite = match edge {
+            Edge::Bottom if self.closed_v => Some(Edge::Top),
+            Edge::Top if self.closed_v => Some(Edge::Bottom),
+            Edge::Left if self.closed_u => Some(Edge::Right),
+            Edge::Right if self.closed_u => Some(Edge::Left),
+            _ => None,
+        };
+        let mut inserted = false;
+        for k in samples {
+            for edge in std::iter::once(edge).chain(opposite) {
+                let (x, y) = self.corner(edge, k);
+                inserted |= self.rows.insert((y, x));
+                self.columns.insert((x, y));
+            }
+        }
+        inserted
+    }
+
+    /// Grid coordinates of vertex `k` along `edge`.
+    fn corner(&self, edge: Edge, k: u32) -> (u32, u32) {
+        match edge {
+            Edge::Bottom => (k, 0),
+            Edge::Top => (k, self.size),
+            Edge::Left => (0, k),
+            Edge::Right => (self.size, k),
+        }
+    }
+
+    /// Key of vertex `k` along `edge` in the vertex lookup of the triangulation, which also
+    /// holds the centers of cells and thus counts in half cells.
+    fn position(&self, edge: Edge, k: u32) -> (u32, u32) {
+        let (x, y) = self.corner(edge, k);
+        (2 * x, 2 * y)
+    }
+}
+
+struct Refiner<'a, S: ?Sized> {
+    surface: &'a S,
+    tolerance: f32,
+    min_cos: f32,
+    min_depth: u32,
+    max_depth: u32,
+}
+
+impl<S: ParametricSurface + Sync + ?Sized> Refiner<'_, S> {
+    /// Leaves of the quadtree below `cell`, refining the children in parallel.
+    fn refine(&self, cell: Cell, depth: u32) -> Vec<Cell> {
+        if depth < self.max_depth && (depth < self.min_depth || !self.is_flat(cell)) {
+            cell.children()
+                .par_iter()
+                .flat_map(|&child| self.refine(child, depth + 1))
+                .collect()
+        } else {
+            vec![cell]
+        }
+    }
+
+    fn is_flat(&self, cell: Cell) -> bool {
+        let scale = 1. / (1u32 << self.max_depth) as f32;
+        let (u0, v0) = (cell.x as f32 * scale, cell.y as f32 * scale);
+        let size = cell.size as f32 * scale;
+        let corners = [(0., 0.), (1., 0.), (0., 1.), (1., 1.)]
+            .map(|(s, t)| self.surface.get_point(u0 + s * size, v0 + t * size));
+        let bilinear = |s: f32, t: f32| {
+            corners[0]
+                .lerp(corners[1], s)
+                .lerp(corners[2].lerp(corners[3], s), t)
+        };
+        let chordal_ok = [(0.5, 0.), (0., 0.5), (0.5, 0.5), (1., 0.5), (0.5, 1.)]
+            .into_iter()
+            .all(|(s, t)| {
+                let point = self.surface.get_point(u0 + s * size, v0 + t * size);
+                point.distance(bilinear(s, t)) <= self.tolerance
+            });
+        if !chordal_ok {
+            return false;
+        }
+
+        let normals = [(0., 0.), (1., 0.), (0., 1.), (1., 1.)]
+            .map(|(s, t)| self.surface.normal(u0 + s * size, v0 + t * size));
+        normals
+            .iter()
+            .all(|n| normals.iter().all(|m| n.dot(*m) >= self.min_cos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use glam::{vec2, vec3, Vec2, Vec3};
+    use test_log::test;
+
+    use super::{Sampling, TessellationBuilder};
+    use crate::bezier::curve::BezierCurve;
+    use crate::bezier::displacement::Displacement;
+    use crate::bezier::patch::BezierPatch;
+    use crate::bezier::surface::BezierSurface;
+    use crate::bezier::trim::{Trim, TrimLoop};
+
+    #[test]
+    fn covers_domain_with_consistent_winding() {
+        let surface = BezierSurface::from(
+            BezierPatch::from_rows([
+                [Vec3::ZERO, Vec3::X, 2. * Vec3::X],
+                [Vec3::Z + Vec3::Y, Vec3::X + Vec3::Z, 2. * Vec3::X + Vec3::Z],
+            ])
+            .unwrap(),
+        );
+        let data = TessellationBuilder::new(5, 4)
+            .with_uv_scale(vec2(2., 3.))
+            .build(&surface)
+            .unwrap();
+        assert_eq!(data.vertices.len(), 20);
+        assert_eq!(data.indices.len(), 4 * 3 * 6);
+        assert_eq!(data.vertices[0].position, Vec3::ZERO);
+        assert_eq!(data.vertices[19].uv, vec2(2., 3.));
+        for tri in data.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| data.vertices[tri[k] as usize]);
+            let face_normal = (b.position - a.position).cross(c.position - a.position);
+            assert!(face_normal.dot(a.normal) > 0.);
+            assert!(a.tangent.dot(a.normal).abs() < 1e-5);
+        }
+
+        assert!(TessellationBuilder::new(1, 10).build(&surface).is_err());
+    }
+
+    #[test]
+    fn background_jobs_hand_out_one_result() {
+        let surface = BezierSurface::from(
+            BezierPatch::from_rows([[Vec3::ZERO, Vec3::X], [Vec3::Z, Vec3::X + Vec3::Z]]).unwrap(),
+        );
+        let job = TessellationBuilder::new(4, 4).spawn(surface.clone());
+        let other = job.clone();
+        assert_eq!(job.wait().unwrap().vertices.len(), 16);
+        assert!(!other.is_done());
+        assert!(matches!(other.try_take(), Some(Err(_))));
+        assert!(other.wait().is_err());
+
+        // Panics are reported instead of leaving the job running forever
+        let job = TessellationBuilder::new(4, 4)
+            .with_displacement(Displacement::new(|_| panic!("no height")))
+            .spawn(surface);
+        let error = job.wait().unwrap_err();
+        assert!(error.to_string().contains("no height"));
+    }
+
+    #[test]
+    fn welds_seams_of_looping_surfaces() {
+        let surface = BezierSurface::new([
+            BezierCurve::new([vec3(1., 0., 0.), vec3(1., 1., 0.), vec3(2., 2., 0.)]),
+            BezierCurve::new([vec3(0., 0., 1.), vec3(0., 1., 1.), vec3(0., 2., 2.)]),
+            BezierCurve::new([vec3(-1., 0., 0.), vec3(-1., 1., 0.), vec3(-2., 2., 0.)]),
+            BezierCurve::new([vec3(0., 0., -1.), vec3(0., 1., -1.), vec3(0., 2., -2.)]),
+        ])
+        .unwrap()
+        .looping(true);
+        let sampling = Sampling::Adaptive {
+            tolerance: 1e-3,
+            max_angle: 0.2,
+            max_depth: 6,
+        };
+        for builder in [
+            TessellationBuilder::new(10, 10),
+            TessellationBuilder::default().with_sampling(sampling),
+        ] {
+            let data = builder.build(&surface).unwrap();
+            let seam = data.vertices.iter().filter(|vertex| vertex.uv.y == 1.);
+            for vertex in seam {
+                assert!(data.vertices.iter().any(|other| other.uv.y == 0.
+                    && other.uv.x == vertex.uv.x
+                    && other.position == vertex.position
+                    && other.normal == vertex.normal));
+            }
+        }
+    }
+
+    #[test]
+    fn adaptive_is_crack_free() {
+        let surface = BezierSurface::from(
+            BezierPatch::from_rows([
+                [
+                    vec3(0., 0., 0.),
+                    vec3(1., 0., 0.),
+                    vec3(2., 0., 0.),
+                    vec3(3., 0., 0.),
+                ],
+                [
+                    vec3(0., 0., 1.),
+                    vec3(1., 0., 1.),
+                    vec3(2., 0., 1.),
+                    vec3(3., 0., 1.),
+                ],
+                [
+                    vec3(0., 0., 2.),
+                    vec3(1., 0., 2.),
+                    vec3(2., 2., 2.),
+                    vec3(3., 0., 2.),
+                ],
+                [
+                    vec3(0., 0., 3.),
+                    vec3(1., 0., 3.),
+                    vec3(2., 0., 3.),
+                    vec3(3., 0., 3.),
+                ],
+            ])
+            .unwrap(),
+        );
+        let sampling = Sampling::Adaptive {
+            tolerance: 1e-3,
+            max_angle: 0.2,
+            max_depth: 6,
+        };
+        let data = TessellationBuilder::default()
+            .with_sampling(sampling)
+            .build(&surface)
+            .unwrap();
+        assert!(data.vertices.len() < 65 * 65 / 2);
+
+        // Every edge is shared by two triangles, except on the border of the domain
+        let mut edges = HashMap::new();
+        for tri in data.indices.chunks_exact(3) {
+            for k in 0..3 {
+                let (a, b) = (tri[k], tri[(k + 1) % 3]);
+                *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+        let on_border = |uv: Vec2| uv.min_element() == 0. || uv.max_element() == 1.;
+        for ((a, b), count) in edges {
+            let (a, b) = (data.vertices[a as usize], data.vertices[b as usize]);
+            if count == 1 {
+                assert!(on_border(a.uv) && on_border(b.uv));
+            } else {
+                assert_eq!(count, 2);
+            }
+        }
+    }
+
+    #[test]
+    fn trimming_follows_loops() {
+        let diamond = [
+            vec2(0.8, 0.5),
+            vec2(0.5, 0.8),
+            vec2(0.2, 0.5),
+            vec2(0.5, 0.2),
+        ];
+        let surface = BezierSurface::from(
+            BezierPatch::from_rows([
+                [vec3(0., 0., 0.), vec3(1., 0., 0.), vec3(2., 0., 0.)],
+                [vec3(0., 0., 1.), vec3(1., 1., 1.), vec3(2., 0., 1.)],
+                [vec3(0., 0., 2.), vec3(1., 0., 2.), vec3(2., 0., 2.)],
+            ])
+            .unwrap(),
+        )
+        .with_trim(Trim::new().with_hole(TrimLoop::polygon(&diamond).unwrap()));
+        let adaptive = Sampling::Adaptive {
+            tolerance: 1e-2,
+            max_angle: 0.3,
+            max_depth: 5,
+        };
+        for sampling in [Sampling::Uniform { u: 7, v: 7 }, adaptive] {
+            let data = TessellationBuilder::default()
+                .with_sampling(sampling)
+                .build(&surface)
+                .unwrap();
+            let mut area = 0.;
+            let mut edges = HashMap::new();
+            for tri in data.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|k| data.vertices[tri[k] as usize].uv);
+                area += (b - a).perp_dot(c - a) / 2.;
+                for k in 0..3 {
+                    let (a, b) = (tri[k], tri[(k + 1) % 3]);
+                    *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+                }
+            }
+            assert!((area - 0.82).abs() < 1e-5);
+
+            let distance = |uv: Vec2| (uv - Vec2::splat(0.5)).abs().dot(Vec2::ONE);
+            let on_border = |uv: Vec2| {
+                uv.min_element() == 0.
+                    || uv.max_element() == 1.
+                    || (distance(uv) - 0.3).abs() < 1e-5
+            };
+            for vertex in &data.vertices {
+                assert!(distance(vertex.uv) > 0.3 - 1e-5);
+            }
+            for ((a, b), count) in edges {
+                let (a, b) = (data.vertices[a as usize], data.vertices[b as usize]);
+                if count == 1 {
+                    assert!(on_border(a.uv) && on_border(b.uv));
+                } else {
+                    assert_eq!(count, 2);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use glam::{vec2, Vec2, Vec3};
+
+use crate::mesh::{MeshData, Vertex};
+
+/// Bézier triangle of degree `n`, evaluated in barycentric coordinates `(u, v, w)` with
+/// `u + v + w = 1`.
+///
+/// Control points `b(i, j, k)` with `i + j + k = n` are stored by rows of increasing `j + k`,
+/// each row going by increasing `k`: `b(n, 0, 0)`, `b(n-1, 1, 0)`, `b(n-1, 0, 1)`, `b(n-2, 2, 0)`…
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriangularPatch {
+    degree: usize,
+    points: Vec<Vec3>,
+}
+
+impl TriangularPatch {
+    pub fn new(degree: usize, points: impl IntoIterator<Item = Vec3>) -> anyhow::Result<Self> {
+        let points = points.into_iter().collect::<Vec<_>>();
+        let expected = (degree + 1) * (degree + 2) / 2;
+        anyhow::ensure!(
+            points.len() == expected,
+            "Triangular patch of degree {} needs {} control points, got {}",
+            degree,
+            expected,
+            points.len()
+        );
+        Ok(Self { degree, points })
+    }
+
+    /// Builds the patch from a function giving the control point `b(i, j, k)`.
+    pub fn from_fn(degree: usize, mut f: impl FnMut(usize, usize, usize) -> Vec3) -> Self {
+        let points = (0..=degree)
+            .flat_map(|r| (0..=r).map(move |k| (degree - r, r - k, k)))
+            .map(|(i, j, k)| f(i, j, k))
+            .collect();
+        Self { degree, points }
+    }
+
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    pub fn get(&self, i: usize, j: usize, k: usize) -> Vec3 {
+        assert_eq!(i + j + k, self.degree, "Invalid control point index");
+        self.points[index(j, k)]
+    }
+
+    pub fn get_mut(&mut self, i: usize, j: usize, k: usize) -> &mut Vec3 {
+        assert_eq!(i + j + k, self.degree, "Invalid control point index");
+        &mut self.points[index(j, k)]
+    }
+
+    pub fn get_point(&self, bary: Vec3) -> Vec3 {
+        self.reduce(bary, 0)[0]
+    }
+
+    /// Unit normal `(∂/∂v - ∂/∂u) × (∂/∂w - ∂/∂u)`, oriented so that the corners `u = 1`,
+    /// `v = 1` and `w = 1` are counter-clockwise around it.
+    pub fn normal(&self, bary: Vec3) -> Vec3 {
+        if self.degree == 0 {
+            return Vec3::ZERO;
+        }
+        let [a, b, c] = match self.reduce(bary, 1)[..] {
+            [a, b, c] => [a, b, c],
+            _ => unreachable!(),
+        };
+        (b - a).cross(c - a).normalize_or_zero()
+    }
+
+    /// Tessellates the patch by splitting each edge into `level` segments.
+    pub fn tessellate(&self, level: usize) -> anyhow::Result<MeshData> {
+        anyhow::ensure!(level > 0, "Tessellation level must be positive");
+        let tangent_hint = self.get(0, self.degree, 0) - self.get(self.degree, 0, 0);
+        let vertices = grid(level)
+            .map(|bary| {
+                let normal = self.normal(bary);
+                Vertex {
+                    position: self.get_point(bary),
+                    normal,
+                    uv: vec2(bary.y, bary.z),
+                    tangent: tangent_hint.reject_from(normal).normalize_or_zero(),
+                    curvature: Vec2::ZERO,
+                }
+            })
+            .collect();
+        Ok(MeshData {
+            vertices,
+            indices: grid_triangles(level).flatten().map(|i| i as u32).collect(),
+        })
+    }
+
+    /// Runs de Casteljau steps down to the control points of degree `degree`.
+    fn reduce(&self, bary: Vec3, degree: usize) -> Vec<Vec3> {
+        let mut points = self.points.clone();
+        for n in (degree..self.degree).rev() {
+            let next = (0..=n)
+                .flat_map(|r| (0..=r).map(move |k| (r - k, k)))
+                .map(|(j, k)| {
+                    bary.x * points[index(j, k)]
+                        + bary.y * points[index(j + 1, k)]
+                        + bary.z * points[index(j, k + 1)]
+                })
+                .collect();
+            points = next;
+        }
+        points
+    }
+}
+
+/// Curved PN triangles (Vlachos et al., 2001) built from the positions and normals of `data`,
+/// each triangle tessellated with `level` segments per edge.
+///
+/// Vertices along edges shared between triangles are shared as well, so that the result stays
+/// connected wherever the input mesh was.
+pub fn pn_triangles(data: &MeshData, level: usize) -> anyhow::Result<MeshData> {
+    anyhow::ensure!(level > 0, "Tessellation level must be positive");
+    anyhow::ensure!(
+        data.indices.chunks_exact(3).remainder().is_empty(),
+        "Mesh indices do not form triangles"
+    );
+    let mut vertices = Vec::new();
+    let mut shared = HashMap::new();
+    let mut indices = Vec::with_capacity(data.indices.len() / 3 * level * level * 3);
+    let local = grid(level).collect::<Vec<_>>();
+
+    for triangle in data.indices.chunks_exact(3) {
+        let ids = [0, 1, 2].map(|k| triangle[k]);
+        let corners = ids.map(|i| data.vertices[i as usize]);
+        let (geometry, normals) = pn_patches(corners);
+        let mapped = local
+            .iter()
+            .map(|&bary| {
+                let mut create = || {
+                    let normal = normals.get_point(bary).normalize_or_zero();
+                    let [a, b, c] = corners;
+                    let uv = bary.x * a.uv + bary.y * b.uv + bary.z * c.uv;
+                    let tangent = bary.x * a.tangent + bary.y * b.tangent + bary.z * c.tangent;
+                    vertices.push(Vertex {
+                        position: geometry.get_point(bary),
+                        normal,
+                        uv,
+                        tangent: tangent.reject_from(normal).normalize_or_zero(),
+                        curvature: Vec2::ZERO,
+                    });
+                    vertices.len() - 1
+                };
+                match share_key(ids, bary, level) {
+                    Some(key) => *shared.entry(key).or_insert_with(create),
+                    None => create(),
+                }
+            })
+            .collect::<Vec<_>>();
+        indices.extend(grid_triangles(level).flatten().map(|i| mapped[i] as u32));
+    }
+
+    Ok(MeshData { vertices, indices })
+}
+
+/// Cubic geometry and quadratic normal patches of a PN triangle.
+fn pn_patches(corners: [Vertex; 3]) -> (TriangularPatch, TriangularPatch) {
+    let [p, n] = [
+        corners.map(|v| v.position),
+        corners.map(|v| v.normal.normalize_or_zero()),
+    ];
+    // Edge control point next to corner `a` on the edge toward corner `b`
+    let edge = |a: usize, b: usize| (2. * p[a] + p[b] - (p[b] - p[a]).dot(n[a]) * n[a]) / 3.;
+    let edges = [
+        ((2, 1, 0), edge(0, 1)),
+        ((1, 2, 0), edge(1, 0)),
+        ((0, 2, 1), edge(1, 2)),
+        ((0, 1, 2), edge(2, 1)),
+        ((1, 0, 2), edge(2, 0)),
+        ((2, 0, 1), edge(0, 2)),
+    ];
+    let average = edges.iter().fold(Vec3::ZERO, |acc, (_, b)| acc + *b) / 6.;
+    let center = (p[0] + p[1] + p[2]) / 3.;
+    let geometry = TriangularPatch::from_fn(3, |i, j, k| match (i, j, k) {
+        (3, 0, 0) => p[0],
+        (0, 3, 0) => p[1],
+        (0, 0, 3) => p[2],
+        (1, 1, 1) => average + (average - center) / 2.,
+        ijk => edges.iter().find(|(key, _)| *key == ijk).unwrap().1,
+    });
+
+    let edge_normal = |a: usize, b: usize| {
+        let d = p[b] - p[a];
+        let v = 2. * d.dot(n[a] + n[b]) / d.length_squared().max(f32::EPSILON);
+        (n[a] + n[b] - v * d).normalize_or_zero()
+    };
+    let normals = TriangularPatch::from_fn(2, |i, j, k| match (i, j, k) {
+        (2, 0, 0) => n[0],
+        (0, 2, 0) => n[1],
+        (0, 0, 2) => n[2],
+        (1, 1, 0) => edge_normal(0, 1),
+        (0, 1, 1) => edge_normal(1, 2),
+        _ => edge_normal(2, 0),
+    });
+    (geometry, normals)
+}
+
+/// Key identifying a tessellation vertex lying on a corner or an edge of the input triangle,
+/// independently of which triangle generates it.
+fn share_key(ids: [u32; 3], bary: Vec3, level: usize) -> Option<(u32, u32, usize)> {
+    let steps = bary.to_array().map(|w| (w * level as f32).round() as usize);
+    match (0..3).filter(|&k| steps[k] == 0).count() {
+        2 => {
+            let corner = (0..3).find(|&k| steps[k] == level).unwrap();
+            Some((ids[corner], ids[corner], 0))
+        }
+        1 => {
+            let zero = (0..3).find(|&k| steps[k] == 0).unwrap();
+            let (a, b) = ((zero + 1) % 3, (zero + 2) % 3);
+            let (a, b) = if ids[a] < ids[b] { (a, b) } else { (b, a) };
+            Some((ids[a], ids[b], steps[b]))
+        }
+        _ => None,
+    }
+}
+
+fn index(j: usize, k: usize) -> usize {
+    let row = j + k;
+    row * (row + 1) / 2 + k
+}
+
+/// Barycentric coordinates of the vertices of a triangle split `level` times along each edge.
+fn grid(level: usize) -> impl Iterator<Item = Vec3> {
+    (0..=level)
+        .flat_map(move |r| (0..=r).map(move |k| (r - k, k)))
+        .map(move |(j, k)| {
+            let (v, w) = (j as f32 / level as f32, k as f32 / level as f32);
+            Vec3::new(1. - v - w, v, w)
+        })
+}
+
+/// Counter-clockwise triangles over the vertices of [`grid`].
+fn grid_triangles(level: usize) -> impl Iterator<Item = [usize; 3]> {
+    (0..level).flat_map(|r| {
+        let up = (0..=r).map(move |k| [index(r - k, k), index(r + 1 - k, k), index(r - k, k + 1)]);
+        let down = (0..r).map(move |k| {
+            [
+                index(r - k, k),
+                index(r - k, k + 1),
+                index(r - k - 1, k + 1),
+            ]
+        });
+        up.chain(down)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec2, vec3, Vec2, Vec3};
+    use test_log::test;
+
+    use super::{pn_triangles, TriangularPatch};
+    use crate::bezier::basis::bernstein;
+    use crate::mesh::{MeshData, Vertex};
+
+    #[test]
+    fn evaluation_matches_bernstein_form() {
+        let patch = TriangularPatch::from_fn(3, |i, j, k| {
+            vec3(i as f32, j as f32, (i * j + k * k) as f32)
+        });
+        let bary = vec3(0.2, 0.5, 0.3);
+        let mut expected = Vec3::ZERO;
+        for i in 0..=3 {
+            for j in 0..=3 - i {
+                let k = 3 - i - j;
+                let coef = bernstein(3, i, bary.x as f64)
+                    * bernstein(3 - i, j, (bary.y / (1. - bary.x)) as f64);
+                expected += coef as f32 * patch.get(i, j, k);
+            }
+        }
+        assert!((patch.get_point(bary) - expected).length() < 1e-4);
+
+        let flat = TriangularPatch::new(1, [Vec3::ZERO, Vec3::X, Vec3::Y]).unwrap();
+        assert!((flat.normal(Vec3::ONE / 3.) - Vec3::Z).length() < 1e-6);
+        let data = flat.tessellate(4).unwrap();
+        assert_eq!(data.vertices.len(), 15);
+        assert_eq!(data.indices.len(), 16 * 3);
+        for tri in data.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| data.vertices[tri[k] as usize].position);
+            assert!((b - a).cross(c - a).z > 0.);
+        }
+    }
+
+    #[test]
+    fn pn_triangles_round_an_octahedron() {
+        let directions = [Vec3::X, Vec3::Y, Vec3::Z, -Vec3::X, -Vec3::Y, -Vec3::Z];
+        let vertices = directions
+            .iter()
+            .map(|&d| Vertex {
+                position: d,
+                normal: d,
+                uv: vec2(0., 0.),
+                tangent: d.any_orthonormal_pair().0,
+                curvature: Vec2::ZERO,
+            })
+            .collect();
+        let indices = vec![
+            0, 1, 2, 1, 3, 2, 3, 4, 2, 4, 0, 2, 1, 0, 5, 3, 1, 5, 4, 3, 5, 0, 4, 5,
+        ];
+        let data = MeshData { vertices, indices };
+        let level = 4;
+        let smooth = pn_triangles(&data, level).unwrap();
+        // Vertices + edges + faces, each shared between triangles
+        let expected = 6 + 12 * (level - 1) + 8 * (level - 1) * (level - 2) / 2;
+        assert_eq!(smooth.vertices.len(), expected);
+        let flat_error = (Vec3::new(1., 1., 1.) / 3.).length() - 1.;
+        for vertex in &smooth.vertices {
+            let error = vertex.position.length() - 1.;
+            assert!(error.abs() < flat_error.abs() / 2.);
+        }
+    }
+}
@@ -0,0 +1,587 @@
+use glam::Vec2;
+
+use crate::bezier::curve::BezierCurve;
+
+/// Maximum distance in parameter space between trim curves and their flattened polylines.
+const FLATNESS: f32 = 1e-4;
+
+/// Closed chain of 2D Bézier curves in the `(u, v)` parameter domain of a surface.
+#[derive(Debug, Clone)]
+pub struct TrimLoop {
+    curves: Vec<BezierCurve<Vec2>>,
+    polyline: Vec<Vec2>,
+}
+
+impl TrimLoop {
+    /// Each curve must start where the previous one ends, and the last curve must end where
+    /// the first one starts. Orientation does not matter.
+    pub fn new(curves: impl IntoIterator<Item = BezierCurve<Vec2>>) -> anyhow::Result<Self> {
+        const EPSILON: f32 = 1e-5;
+        let curves = curves.into_iter().collect::<Vec<_>>();
+        anyhow::ensure!(!curves.is_empty(), "Trim loop has no curves");
+        let ends = curves
+            .iter()
+            .enumerate()
+            .map(|(k, curve)| {
+                let points = curve.control_points();
+                anyhow::ensure!(
+                    points.len() >= 2,
+                    "Trim curve {} needs at least 2 control points",
+                    k
+                );
+                Ok((points[0], points[points.len() - 1]))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        for k in 0..ends.len() {
+            let next = (k + 1) % ends.len();
+            anyhow::ensure!(
+                ends[k].1.distance(ends[next].0) <= EPSILON,
+                "Trim curve {} does not end where curve {} starts",
+                k,
+                next
+            );
+        }
+
+        let mut polyline = Vec::new();
+        for curve in &curves {
+            flatten(&curve.control_points(), &mut polyline);
+        }
+        anyhow::ensure!(
+            area(&polyline).abs() > f32::EPSILON,
+            "Trim loop encloses no area"
+        );
+        Ok(Self { curves, polyline })
+    }
+
+    /// Loop made of straight segments between consecutive points.
+    pub fn polygon(points: &[Vec2]) -> anyhow::Result<Self> {
+        Self::new(
+            (0..points.len())
+                .map(|k| BezierCurve::new([points[k], points[(k + 1) % points.len()]])),
+        )
+    }
+
+    pub fn curves(&self) -> &[BezierCurve<Vec2>] {
+        &self.curves
+    }
+
+    /// Flattened loop, without repeating the first point at the end.
+    pub fn polyline(&self) -> &[Vec2] {
+        &self.polyline
+    }
+
+    pub fn contains(&self, uv: Vec2) -> bool {
+        contains(&self.polyline, uv)
+    }
+}
+
+/// Trimming of a surface: the part of the parameter domain inside the outer loop, if any, and
+/// outside of every hole. Loops must not intersect each other.
+#[derive(Debug, Clone, Default)]
+pub struct Trim {
+    outer: Option<TrimLoop>,
+    holes: Vec<TrimLoop>,
+}
+
+impl Trim {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_outer(mut self, outer: TrimLoop) -> Self {
+        self.outer = Some(outer);
+        self
+    }
+
+    pub fn with_hole(mut self, hole: TrimLoop) -> Self {
+        self.holes.push(hole);
+        self
+    }
+
+    pub fn outer(&self) -> Option<&TrimLoop> {
+        self.outer.as_ref()
+    }
+
+    pub fn holes(&self) -> &[TrimLoop] {
+        &self.holes
+    }
+
+    /// Whether the parameter `uv` is kept by the trimming.
+    pub fn contains(&self, uv: Vec2) -> bool {
+        self.outer.iter().all(|outer| outer.contains(uv))
+            && !self.holes.iter().any(|hole| hole.contains(uv))
+    }
+
+    /// Flattened loops, oriented so that the kept region is on their left.
+    pub(crate) fn region(&self) -> Region {
+        let oriented = |trim: &TrimLoop, ccw: bool| {
+            let mut points = trim.polyline.clone();
+            if (area(&points) > 0.) != ccw {
+                points.reverse();
+            }
+            points
+        };
+        Region {
+            trim: self.clone(),
+            loops: self
+                .outer
+                .iter()
+                .map(|outer| oriented(outer, true))
+                .chain(self.holes.iter().map(|hole| oriented(hole, false)))
+                .collect(),
+        }
+    }
+}
+
+/// Flattened [`Trim`], used to clip tessellation cells.
+pub(crate) struct Region {
+    trim: Trim,
+    loops: Vec<Vec<Vec2>>,
+}
+
+/// Crossing of a trim loop with the boundary of a window.
+#[derive(Debug, Copy, Clone)]
+struct Crossing {
+    point: Vec2,
+    /// Position along the boundary of the window, counter-clockwise from its lower left corner.
+    s: f32,
+}
+
+/// Part of a trim loop inside a window, from its entry to its exit.
+#[derive(Debug, Clone)]
+struct Path {
+    entry: Crossing,
+    points: Vec<Vec2>,
+    exit: Crossing,
+}
+
+impl Region {
+    /// Triangulates the intersection of the region with a window: an axis-aligned rectangle
+    /// given by its boundary points in counter-clockwise order, corners included.
+    ///
+    /// Points on the left and bottom edges of the window count as inside, points on the right
+    /// and top edges as outside, so that loops running along cell boundaries are assigned to a
+    /// single cell. Crossings only depend on the trim segment and the coordinate of the crossed
+    /// edge, so that neighboring windows share their boundary vertices.
+    pub(crate) fn clip(&self, window: &[Vec2]) -> Vec<[Vec2; 3]> {
+        let (min, max) = window.iter().fold(
+            (Vec2::splat(f32::INFINITY), Vec2::splat(-f32::INFINITY)),
+            |(a, b), &p| (a.min(p), b.max(p)),
+        );
+        let rect = Rect { min, max };
+
+        let mut paths = Vec::new();
+        let mut islands = Vec::new();
+        for points in &self.loops {
+            match rect.paths(points) {
+                Some(found) => paths.extend(found),
+                None if rect.contains(points[0]) => islands.push(points.clone()),
+                None => {}
+            }
+        }
+
+        let mut polygons = Vec::new();
+        if paths.is_empty() {
+            if self.trim.contains((min + max) / 2.) {
+                polygons.push((window.to_vec(), Vec::new()));
+            }
+        } else {
+            // Weiler-Atherton with a convex window: from each exit, follow the window
+            // counter-clockwise up to the next entry
+            let boundary = window
+                .iter()
+                .map(|&p| (rect.boundary_position(p), p))
+                .collect::<Vec<_>>();
+            let mut used = vec![false; paths.len()];
+            while let Some(start) = used.iter().position(|used| !used) {
+                let mut polygon = Vec::new();
+                let mut current = start;
+                loop {
+                    used[current] = true;
+                    let path = &paths[current];
+                    polygon.push(path.entry.point);
+                    polygon.extend(&path.points);
+                    polygon.push(path.exit.point);
+                    let distance = |s: f32| (s - path.exit.s).rem_euclid(4.);
+                    let next = (0..paths.len())
+                        .min_by(|&a, &b| {
+                            distance(paths[a].entry.s).total_cmp(&distance(paths[b].entry.s))
+                        })
+                        .unwrap();
+                    let until = distance(paths[next].entry.s);
+                    let mut corners = boundary
+                        .iter()
+                        .map(|&(s, p)| (distance(s), p))
+                        .filter(|&(d, _)| d > 0. && d < until)
+                        .collect::<Vec<_>>();
+                    corners.sort_by(|a, b| a.0.total_cmp(&b.0));
+                    polygon.extend(corners.into_iter().map(|(_, p)| p));
+                    if used[next] {
+                        break;
+                    }
+                    current = next;
+                }
+                polygons.push((polygon, Vec::new()));
+            }
+        }
+
+        // Loops entirely inside the window are either the outer loop or holes
+        for island in islands {
+            if area(&island) > 0. {
+                polygons.push((island, Vec::new()));
+            } else if let Some((_, holes)) = polygons
+                .iter_mut()
+                .find(|(polygon, _)| contains(polygon, island[0]))
+            {
+                holes.push(island);
+            }
+        }
+
+        polygons
+            .into_iter()
+            .flat_map(|(polygon, holes)| triangulate(polygon, holes))
+            .collect()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Rect {
+    min: Vec2,
+    max: Vec2,
+}
+
+impl Rect {
+    /// Sides of the point relative to the left, right, bottom and top edges.
+    fn sides(&self, p: Vec2) -> [bool; 4] {
+        [
+            p.x < self.min.x,
+            p.x >= self.max.x,
+            p.y < self.min.y,
+            p.y >= self.max.y,
+        ]
+    }
+
+    fn contains(&self, p: Vec2) -> bool {
+        self.sides(p) == [false; 4]
+    }
+
+    /// Position of a point of the boundary, counter-clockwise from the lower left corner, with
+    /// each edge of length 1.
+    fn boundary_position(&self, p: Vec2) -> f32 {
+        let size = self.max - self.min;
+        if p.y == self.min.y && p.x < self.max.x {
+            (p.x - self.min.x) / size.x
+        } else if p.x == self.max.x && p.y < self.max.y {
+            1. + (p.y - self.min.y) / size.y
+        } else if p.y == self.max.y && p.x > self.min.x {
+            2. + (self.max.x - p.x) / size.x
+        } else {
+            3. + (self.max.y - p.y) / size.y
+        }
+    }
+
+    /// Pieces of a closed polyline inside the rectangle, or `None` if it never crosses it.
+    fn paths(&self, points: &[Vec2]) -> Option<Vec<Path>> {
+        enum Event {
+            Vertex(Vec2),
+            Enter(Crossing),
+            Exit(Crossing),
+        }
+        let mut events = Vec::new();
+        let mut sides = self.sides(points[0]);
+        for (k, &a) in points.iter().enumerate() {
+            let b = points[(k + 1) % points.len()];
+            let target = self.sides(b);
+            let lines = [
+                (0, self.min.x),
+                (0, self.max.x),
+                (1, self.min.y),
+                (1, self.max.y),
+            ];
+            let mut crossings = (0..4)
+                .filter(|&edge| sides[edge] != target[edge])
+                .map(|edge| {
+                    let (axis, c) = lines[edge];
+                    let t = (c - a[axis]) / (b[axis] - a[axis]);
+                    let mut point = a.lerp(b, t);
+                    point[axis] = c;
+                    (t, edge, point)
+                })
+                .collect::<Vec<_>>();
+            crossings.sort_by(|x, y| x.0.total_cmp(&y.0));
+            for (_, edge, point) in crossings {
+                let inside = sides == [false; 4];
+                sides[edge] = target[edge];
+                let crossing = |point: Vec2| Crossing {
+                    point,
+                    s: self.boundary_position(point),
+                };
+                match (inside, sides == [false; 4]) {
+                    (false, true) => events.push(Event::Enter(crossing(point))),
+                    (true, false) => events.push(Event::Exit(crossing(point))),
+                    _ => {}
+                }
+            }
+            if self.contains(b) {
+                events.push(Event::Vertex(b));
+            }
+        }
+
+        let start = events.iter().position(|e| matches!(e, Event::Exit(_)))? + 1;
+        let len = events.len();
+        events.rotate_left(start % len);
+        let mut paths = Vec::new();
+        let mut current = None;
+        for event in events {
+            match event {
+                Event::Enter(entry) => current = Some((entry, Vec::new())),
+                Event::Vertex(p) => {
+                    if let Some((_, points)) = &mut current {
+                        points.push(p);
+                    }
+                }
+                Event::Exit(exit) => {
+                    // Loops touching the window at a single point do not split it
+                    let touching = |(entry, points): &(Crossing, Vec<Vec2>)| {
+                        entry.point == exit.point && points.iter().all(|&p| p == exit.point)
+                    };
+                    if let Some((entry, points)) = current.take().filter(|c| !touching(c)) {
+                        paths.push(Path {
+                            entry,
+                            points,
+                            exit,
+                        });
+                    }
+                }
+            }
+        }
+        Some(paths)
+    }
+}
+
+/// Appends the flattened curve given by its control points, without its first point.
+fn flatten(points: &[Vec2], out: &mut Vec<Vec2>) {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let chord = last - first;
+    let flat = points[1..points.len() - 1].iter().all(|&p| {
+        let offset = p - first;
+        let distance = match chord.length() {
+            l if l > f32::EPSILON => chord.perp_dot(offset).abs() / l,
+            _ => offset.length(),
+        };
+        distance <= FLATNESS
+    });
+    if flat {
+        out.push(last);
+        return;
+    }
+    // Split at the middle with de Casteljau
+    let mut left = Vec::with_capacity(points.len());
+    let mut right = Vec::with_capacity(points.len());
+    let mut current = points.to_vec();
+    while !current.is_empty() {
+        left.push(current[0]);
+        right.push(current[current.len() - 1]);
+        current = current.windows(2).map(|w| w[0].lerp(w[1], 0.5)).collect();
+    }
+    right.reverse();
+    flatten(&left, out);
+    flatten(&right, out);
+}
+
+/// Signed area of a polygon, positive when counter-clockwise.
+fn area(points: &[Vec2]) -> f32 {
+    (0..points.len())
+        .map(|k| points[k].perp_dot(points[(k + 1) % points.len()]))
+        .sum::<f32>()
+        / 2.
+}
+
+/// Even-odd point in polygon test.
+fn contains(points: &[Vec2], p: Vec2) -> bool {
+    let mut inside = false;
+    for k in 0..points.len() {
+        let (a, b) = (points[k], points[(k + 1) % points.len()]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Whether segments `ab` and `cd` cross at a point interior to both.
+fn segments_cross(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> bool {
+    let side = |p: Vec2, q: Vec2, r: Vec2| (q - p).perp_dot(r - p);
+    let (d1, d2) = (side(a, b, c), side(a, b, d));
+    let (d3, d4) = (side(c, d, a), side(c, d, b));
+    d1 * d2 < 0. && d3 * d4 < 0.
+}
+
+/// Ear-clipping triangulation of a counter-clockwise polygon with clockwise holes.
+///
+/// Collinear vertices are clipped as degenerate triangles rather than skipped, so that every
+/// boundary vertex stays connected to the mesh.
+fn triangulate(mut polygon: Vec<Vec2>, mut holes: Vec<Vec<Vec2>>) -> Vec<[Vec2; 3]> {
+    // Bridge holes to the outer boundary, rightmost holes first
+    let rightmost = |hole: &[Vec2]| {
+        (0..hole.len())
+            .max_by(|&a, &b| hole[a].x.total_cmp(&hole[b].x))
+            .unwrap()
+    };
+    holes.sort_by(|a, b| a[rightmost(a)].x.total_cmp(&b[rightmost(b)].x));
+    while let Some(hole) = holes.pop() {
+        let m = rightmost(&hole);
+        let from = hole[m];
+        let edges = polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .chain(
+                holes
+                    .iter()
+                    .chain([&hole])
+                    .flat_map(|h| h.iter().zip(h.iter().cycle().skip(1))),
+            )
+            .map(|(&a, &b)| (a, b))
+            .collect::<Vec<_>>();
+        let mut candidates = (0..polygon.len()).collect::<Vec<_>>();
+        candidates.sort_by(|&a, &b| {
+            polygon[a]
+                .distance_squared(from)
+                .total_cmp(&polygon[b].distance_squared(from))
+        });
+        let visible = candidates
+            .iter()
+            .copied()
+            .find(|&k| {
+                edges
+                    .iter()
+                    .all(|&(a, b)| !segments_cross(from, polygon[k], a, b))
+            })
+            .unwrap_or(candidates[0]);
+        let bridge = polygon[visible];
+        let mut spliced = polygon[..=visible].to_vec();
+        spliced.extend(hole[m..].iter().chain(&hole[..=m]));
+        spliced.push(bridge);
+        spliced.extend(&polygon[visible + 1..]);
+        polygon = spliced;
+    }
+    polygon.dedup();
+    while polygon.len() > 1 && polygon[0] == polygon[polygon.len() - 1] {
+        polygon.pop();
+    }
+
+    let mut triangles = Vec::with_capacity(polygon.len().saturating_sub(2));
+    while polygon.len() > 3 {
+        let n = polygon.len();
+        let corner = |k: usize| {
+            let (a, b, c) = (polygon[(k + n - 1) % n], polygon[k], polygon[(k + 1) % n]);
+            ((b - a).perp_dot(c - b), [a, b, c])
+        };
+        let is_ear = |k: usize| {
+            let (turn, [a, b, c]) = corner(k);
+            turn >= 0.
+                && polygon.iter().all(|&p| {
+                    p == a
+                        || p == b
+                        || p == c
+                        || [(a, b), (b, c), (c, a)]
+                            .iter()
+                            .any(|&(q, r)| (r - q).perp_dot(p - q) <= 0.)
+                })
+        };
+        // Fall back to the most convex vertex on numerically inconsistent polygons
+        let ear = (0..n).find(|&k| is_ear(k)).unwrap_or_else(|| {
+            (0..n)
+                .max_by(|&a, &b| corner(a).0.total_cmp(&corner(b).0))
+                .unwrap()
+        });
+        triangles.push(corner(ear).1);
+        polygon.remove(ear);
+    }
+    if let [a, b, c] = polygon[..] {
+        triangles.push([a, b, c]);
+    }
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    use glam::{vec2, Vec2};
+    use test_log::test;
+
+    use super::{Trim, TrimLoop};
+    use crate::bezier::curve::BezierCurve;
+
+    /// Circle approximated by four cubic arcs.
+    fn circle(center: Vec2, radius: f32) -> TrimLoop {
+        const K: f32 = 0.552_284_8;
+        let arcs = [Vec2::X, Vec2::Y, -Vec2::X, -Vec2::Y].map(|dir| {
+            let next = dir.perp();
+            BezierCurve::new([
+                center + radius * dir,
+                center + radius * (dir + K * next),
+                center + radius * (next + K * dir),
+                center + radius * next,
+            ])
+        });
+        TrimLoop::new(arcs).unwrap()
+    }
+
+    fn clipped_area(trim: &Trim, cells: usize) -> f32 {
+        let region = trim.region();
+        let step = 1. / cells as f32;
+        let mut area = 0.;
+        for j in 0..cells {
+            for i in 0..cells {
+                let (u0, v0) = (i as f32 * step, j as f32 * step);
+                let (u1, v1) = ((i + 1) as f32 * step, (j + 1) as f32 * step);
+                let window = [vec2(u0, v0), vec2(u1, v0), vec2(u1, v1), vec2(u0, v1)];
+                for [a, b, c] in region.clip(&window) {
+                    let doubled = (b - a).perp_dot(c - a);
+                    assert!(doubled >= -1e-6, "Triangle is wound clockwise");
+                    area += doubled / 2.;
+                }
+            }
+        }
+        area
+    }
+
+    #[test]
+    fn clipping_preserves_area() {
+        let square = |min: f32, max: f32| {
+            TrimLoop::polygon(&[
+                vec2(min, min),
+                vec2(max, min),
+                vec2(max, max),
+                vec2(min, max),
+            ])
+            .unwrap()
+        };
+        // Loops along cell boundaries and across the domain boundary
+        assert!((clipped_area(&Trim::new().with_hole(square(0.25, 0.75)), 4) - 0.75).abs() < 1e-5);
+        assert!((clipped_area(&Trim::new().with_outer(square(0., 1.)), 4) - 1.).abs() < 1e-5);
+
+        let triangle =
+            TrimLoop::polygon(&[vec2(0.1, 0.1), vec2(0.9, 0.1), vec2(0.1, 0.9)]).unwrap();
+        for cells in [1, 3, 8] {
+            let area = clipped_area(&Trim::new().with_outer(triangle.clone()), cells);
+            assert!((area - 0.32).abs() < 1e-5);
+        }
+
+        // Holes larger and smaller than cells, and an outer loop with a hole
+        let ring = Trim::new()
+            .with_outer(circle(vec2(0.5, 0.5), 0.45))
+            .with_hole(circle(vec2(0.4, 0.5), 0.05))
+            .with_hole(circle(vec2(0.65, 0.55), 0.2));
+        let expected = std::f32::consts::PI * (0.45f32.powi(2) - 0.05f32.powi(2) - 0.2f32.powi(2));
+        for cells in [2, 7] {
+            assert!((clipped_area(&ring, cells) - expected).abs() < 1e-3);
+        }
+
+        assert!(ring.contains(vec2(0.5, 0.2)));
+        assert!(!ring.contains(vec2(0.4, 0.5)));
+        assert!(!ring.contains(vec2(0.5 + FRAC_1_SQRT_2 * 0.5, 0.9)));
+    }
+}
@@ -1,15 +1,21 @@
 use std::time::Duration;
 
-use glam::{vec2, vec3, Quat, Vec3};
-use glutin::{dpi::PhysicalSize, event::WindowEvent};
+use glam::{vec2, vec3, Quat, Vec2, Vec3};
+use glutin::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent},
+};
 
 use iafa_ig_projet::light::LightBuffer;
 use iafa_ig_projet::{
-    bezier::{curve::BezierCurve, surface::BezierSurface},
+    bezier::{
+        curve::BezierCurve, lod::SurfaceLod, surface::BezierSurface,
+        tessellation::TessellationBuilder,
+    },
     camera::{Camera, Projection},
     gbuffers::GeometryBuffers,
     light::{GpuLight, Light},
-    material::Material,
+    material::{Material, Shading},
     mesh::Mesh,
     run,
     transform::Transform,
@@ -25,13 +31,15 @@ use violette_low::{
 struct App {
     surface: BezierSurface,
     bezier_mesh: Option<Mesh>,
+    lod: SurfaceLod,
     lights: LightBuffer,
     mat: Material,
     cam: Camera,
     screen_pass: GeometryBuffers,
+    cursor: Vec2,
 }
 
-fn bsurface() -> BezierSurface {
+fn bsurface() -> anyhow::Result<BezierSurface> {
     BezierSurface::new([
         BezierCurve::new(
             [
@@ -91,8 +99,13 @@ impl Application for App {
         })?;
 
         Ok(Self {
-            surface: bsurface(),
+            surface: bsurface()?,
             bezier_mesh: None,
+            lod: SurfaceLod::new(0.5).with_builder(
+                TessellationBuilder::default()
+                    .with_uv_scale(Vec2::splat(2.))
+                    .with_curvature(true),
+            ),
             lights: GpuLight::create_buffer([
                 Light::Directional {
                     color: vec3(2.5, 2.6, 2.1),
@@ -126,6 +139,7 @@ impl Application for App {
                 },
             },
             screen_pass,
+            cursor: Vec2::ZERO,
         })
     }
 
@@ -143,7 +157,57 @@ impl Application for App {
             .viewport(0, 0, size.width as _, size.height as _);
     }
 
-    fn interact(&mut self, _event: WindowEvent) {}
+    fn interact(&mut self, event: WindowEvent) {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = position.cast();
+                self.cursor = Vec2::new(position.x, position.y);
+            }
+            WindowEvent::MouseInput {
+                button: MouseButton::Left,
+                state: ElementState::Pressed,
+                ..
+            } => {
+                let model = self
+                    .bezier_mesh
+                    .as_ref()
+                    .map(|mesh| mesh.transform.matrix())
+                    .unwrap_or_default()
+                    .inverse();
+                let (origin, dir) = self.cam.ray(self.cursor);
+                let (origin, dir) = (model.transform_point3(origin), model.transform_vector3(dir));
+                match self.surface.intersect_ray(origin, dir) {
+                    Some((u, v, t, point, normal)) => {
+                        tracing::info!(message = "Picked surface", u, v, t, ?point, ?normal)
+                    }
+                    None => tracing::info!("Missed surface"),
+                }
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(key @ (VirtualKeyCode::C | VirtualKeyCode::Z)),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                let shading = match (key, self.mat.shading()) {
+                    (VirtualKeyCode::C, Shading::GaussianCurvature { .. }) => {
+                        Shading::MeanCurvature { range: 2. }
+                    }
+                    (VirtualKeyCode::C, Shading::MeanCurvature { .. }) => Shading::Albedo,
+                    (VirtualKeyCode::C, _) => Shading::GaussianCurvature { range: 4. },
+                    (_, Shading::Zebra { .. }) => Shading::Isophotes { stripes: 12. },
+                    (_, Shading::Isophotes { .. }) => Shading::Albedo,
+                    (_, _) => Shading::Zebra { stripes: 16. },
+                };
+                tracing::info!(?shading);
+                self.mat.set_shading(shading).unwrap();
+            }
+            _ => {}
+        }
+    }
 
     fn tick(&mut self, dt: Duration) {
         //self.cam.transform.rotation *= Quat::from_rotation_y(dt.as_secs_f32() * 0.4);
@@ -153,13 +217,29 @@ impl Application for App {
     }
 
     fn render(&mut self) {
-        let mesh = self
+        let transform = self
             .bezier_mesh
-            .get_or_insert_with(|| self.surface.triangulate(100, 100).unwrap());
+            .as_ref()
+            .map(|mesh| mesh.transform)
+            .unwrap_or_default();
+        // Tessellation runs on worker threads; keep drawing the previous mesh until it is done
+        if let Some(data) = self
+            .lod
+            .update_in_background(&self.surface, &transform, &self.cam)
+            .unwrap()
+        {
+            let mut mesh = data.upload().unwrap();
+            mesh.transform = transform;
+            self.bezier_mesh = Some(mesh);
+        }
         self.screen_pass
             .framebuffer()
             .with_binding(|frame| {
                 frame.do_clear(ClearBuffer::COLOR)?;
+                let mesh = match &mut self.bezier_mesh {
+                    Some(mesh) => mesh,
+                    None => return Ok(()),
+                };
                 let mut lights = self.lights.bind()?;
                 self.mat
                     .draw_mesh(frame, &self.cam, &mut *lights, std::array::from_mut(mesh))
@@ -1,6 +1,6 @@
 use std::ops::Range;
 
-use glam::Mat4;
+use glam::{vec3, Mat4, Vec2, Vec3};
 use glutin::dpi::PhysicalSize;
 
 use crate::transform::Transform;
@@ -38,6 +38,11 @@ impl Projection {
             self.zrange.end,
         )
     }
+
+    /// World-space size covered by one pixel at the given distance from the eye.
+    pub fn pixel_size(&self, distance: f32) -> f32 {
+        2. * distance * (self.fovy / 2.).tan() / self.height
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -45,3 +50,24 @@ pub struct Camera {
     pub transform: Transform,
     pub projection: Projection,
 }
+
+impl Camera {
+    /// World-space position of the eye; the transform holds the view matrix.
+    pub fn position(&self) -> Vec3 {
+        self.transform
+            .matrix()
+            .inverse()
+            .transform_point3(Vec3::ZERO)
+    }
+
+    /// World-space ray through the given window position, in pixels from the top-left corner, as
+    /// an origin on the near plane and a unit direction.
+    pub fn ray(&self, position: Vec2) -> (Vec3, Vec3) {
+        let inverse = (self.projection.matrix() * self.transform.matrix()).inverse();
+        let x = 2. * position.x / self.projection.width - 1.;
+        let y = 1. - 2. * position.y / self.projection.height;
+        let near = inverse.project_point3(vec3(x, y, -1.));
+        let far = inverse.project_point3(vec3(x, y, 1.));
+        (near, (far - near).normalize())
+    }
+}
@@ -22,6 +22,7 @@ pub mod bezier;
 pub mod camera;
 pub mod gbuffers;
 pub mod light;
+pub mod mass;
 pub mod material;
 pub mod mesh;
 pub mod screen_draw;
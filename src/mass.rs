@@ -0,0 +1,139 @@
+use glam::{DMat3, DVec3, Mat3, Vec3};
+
+use crate::mesh::MeshData;
+
+/// Barycentric coordinates and weights of a triangle quadrature exact for cubic polynomials.
+const TRIANGLE_QUADRATURE: [([f32; 3], f32); 4] = [
+    ([1. / 3., 1. / 3., 1. / 3.], -27. / 48.),
+    ([0.6, 0.2, 0.2], 25. / 48.),
+    ([0.2, 0.6, 0.2], 25. / 48.),
+    ([0.2, 0.2, 0.6], 25. / 48.),
+];
+
+/// Area, and volume quantities of the solid enclosed by a closed shell of unit density.
+///
+/// Volume quantities are obtained from the divergence theorem, so they are only meaningful for
+/// closed shells, and come out negative for shells facing inward.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MassProperties {
+    pub area: f32,
+    pub volume: f32,
+    /// Center of mass, or zero for a shell enclosing no volume.
+    pub centroid: Vec3,
+    /// Inertia tensor about the centroid.
+    pub inertia: Mat3,
+}
+
+/// Accumulates the integrals of mass properties over surface elements.
+#[derive(Debug, Default)]
+pub(crate) struct Moments {
+    area: f64,
+    volume: f64,
+    first: DVec3,
+    /// Integrals of `x_i x_j` over the volume.
+    second: [[f64; 3]; 3],
+}
+
+impl Moments {
+    /// Adds the surface element at `point` with quadrature `weight`, given the vector area
+    /// `normal` (area times unit normal) of the element it samples.
+    pub fn add(&mut self, point: Vec3, normal: Vec3, weight: f32) {
+        let (p, n) = (point.as_dvec3(), normal.as_dvec3());
+        self.area += weight as f64 * n.length();
+        let n = weight as f64 * n;
+        self.volume += p.dot(n) / 3.;
+        self.first += p * p * n / 2.;
+        let (p, n) = (p.to_array(), n.to_array());
+        for i in 0..3 {
+            self.second[i][i] += p[i].powi(3) * n[i] / 3.;
+            for j in 0..3 {
+                if i != j {
+                    self.second[i][j] +=
+                        (p[i] * p[i] * p[j] * n[i] + p[j] * p[j] * p[i] * n[j]) / 4.;
+                }
+            }
+        }
+    }
+
+    pub fn finish(&self) -> MassProperties {
+        if self.volume.abs() <= f64::EPSILON * self.area.max(1.).powf(1.5) {
+            return MassProperties {
+                area: self.area as f32,
+                volume: 0.,
+                centroid: Vec3::ZERO,
+                inertia: Mat3::ZERO,
+            };
+        }
+        let centroid = self.first / self.volume;
+        // Parallel axis theorem brings the second moments to the centroid
+        let second = DMat3::from_cols_array_2d(&self.second)
+            - DMat3::from_cols(
+                centroid * centroid.x,
+                centroid * centroid.y,
+                centroid * centroid.z,
+            ) * self.volume;
+        let trace = second.x_axis.x + second.y_axis.y + second.z_axis.z;
+        let inertia = DMat3::from_diagonal(DVec3::splat(trace)) - second;
+        MassProperties {
+            area: self.area as f32,
+            volume: self.volume as f32,
+            centroid: centroid.as_vec3(),
+            inertia: inertia.as_mat3(),
+        }
+    }
+}
+
+impl MeshData {
+    /// Mass properties of the triangles, see [`MassProperties`].
+    pub fn mass_properties(&self) -> MassProperties {
+        let mut moments = Moments::default();
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] =
+                [triangle[0], triangle[1], triangle[2]].map(|i| self.vertices[i as usize].position);
+            let normal = (b - a).cross(c - a) / 2.;
+            for ([wa, wb, wc], weight) in TRIANGLE_QUADRATURE {
+                moments.add(wa * a + wb * b + wc * c, normal, weight);
+            }
+        }
+        moments.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Mat3, Vec3};
+    use test_log::test;
+
+    use crate::bezier::patch::BezierPatch;
+    use crate::bezier::surface::BezierSurface;
+    use crate::bezier::tessellation::TessellationBuilder;
+
+    #[test]
+    fn box_mass_properties() {
+        // A 3 × 2 rectangle thickened by 1 is a box
+        let plane = BezierSurface::from(
+            BezierPatch::from_rows([
+                [vec3(1., 0., 0.), vec3(1., 0., 2.)],
+                [vec3(4., 0., 0.), vec3(4., 0., 2.)],
+            ])
+            .unwrap(),
+        );
+        let solid = TessellationBuilder::new(3, 3)
+            .build_solid(&plane, 1.)
+            .unwrap();
+        let properties = solid.mass_properties();
+        assert!((properties.area - 22.).abs() < 1e-4);
+        assert!((properties.volume - 6.).abs() < 1e-4);
+        let offset = plane.normal(0.5, 0.5) / 2.;
+        assert!((properties.centroid - (vec3(2.5, 0., 1.) + offset)).length() < 1e-4);
+        // I_xx = m (b² + c²) / 12 for a box of sides a, b, c
+        let expected = Mat3::from_diagonal(vec3(1. + 4., 9. + 4., 9. + 1.) * 6. / 12.);
+        assert!(properties.inertia.abs_diff_eq(expected, 1e-3));
+
+        // Open shells enclose nothing
+        let open = TessellationBuilder::new(3, 3).build(&plane).unwrap();
+        let properties = open.mass_properties();
+        assert!((properties.area - 6.).abs() < 1e-4);
+        assert_eq!(properties.centroid, Vec3::ZERO);
+    }
+}
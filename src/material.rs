@@ -96,12 +96,37 @@ impl ShaderBuilder {
     }
 }
 
+/// How a [`Material`] colors the meshes it draws.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Shading {
+    /// Albedo taken from the color slot of the material.
+    Albedo,
+    /// False color of the baked Gaussian curvature, saturating at `±range`.
+    GaussianCurvature { range: f32 },
+    /// False color of the baked mean curvature, saturating at `±range`.
+    MeanCurvature { range: f32 },
+    /// Unlit reflection of `stripes` parallel light tubes, whose kinks and breaks reveal normal
+    /// (G1) and curvature (G2) discontinuities.
+    Zebra { stripes: f32 },
+    /// Unlit lines of equal incidence between the view direction and the normal, `stripes` of
+    /// them between grazing and head-on.
+    Isophotes { stripes: f32 },
+}
+
+impl Shading {
+    /// Whether the meshes are drawn once per light of the scene.
+    fn is_lit(&self) -> bool {
+        !matches!(self, Self::Zebra { .. } | Self::Isophotes { .. })
+    }
+}
+
 pub struct Material {
     program: Program<Linked>,
     color_slot: TextureSlot<3>,
     normal_map: Option<Texture<[f32; 3]>>,
     rough_metal: TextureSlot<2>,
     normal_amount: f32,
+    shading: Shading,
 }
 
 impl Material {
@@ -113,18 +138,83 @@ impl Material {
         let mut color_slot = color_slot.into();
         let mut normal_map = normal_map.into();
         let mut rough_metal = rough_metal.into();
+        let shading = Shading::Albedo;
+        let program = Self::link(shading, &mut color_slot, &mut normal_map, &mut rough_metal)?;
+        Ok(Self {
+            program,
+            color_slot,
+            normal_map,
+            normal_amount: 1.,
+            rough_metal,
+            shading,
+        })
+    }
+
+    pub fn with_normal_amount(mut self, amt: f32) -> anyhow::Result<Material> {
+        self.normal_amount = amt;
+        self.set_normal_amount()?;
+        Ok(self)
+    }
+
+    pub fn with_shading(mut self, shading: Shading) -> anyhow::Result<Material> {
+        self.set_shading(shading)?;
+        Ok(self)
+    }
+
+    pub fn shading(&self) -> Shading {
+        self.shading
+    }
+
+    /// Switches shading mode, relinking the shader program.
+    pub fn set_shading(&mut self, shading: Shading) -> anyhow::Result<()> {
+        self.program = Self::link(
+            shading,
+            &mut self.color_slot,
+            &mut self.normal_map,
+            &mut self.rough_metal,
+        )?;
+        self.shading = shading;
+        self.set_normal_amount()
+    }
 
+    fn set_normal_amount(&mut self) -> anyhow::Result<()> {
+        let normal_amount = self.normal_amount;
+        self.program
+            .with_binding(|prog| {
+                if let Some(loc) = prog.uniform("normal_amount") {
+                    loc.set(normal_amount)
+                } else {
+                    Ok(())
+                }
+            })
+            .context("Cannot set normal amount in material")
+    }
+
+    fn link(
+        shading: Shading,
+        color_slot: &mut TextureSlot<3>,
+        normal_map: &mut Option<Texture<[f32; 3]>>,
+        rough_metal: &mut TextureSlot<2>,
+    ) -> anyhow::Result<Program<Linked>> {
         let shaders_dir = Path::new("assets").join("shaders");
         let vert_shader = Shader::load(ShaderStage::Vertex, shaders_dir.join("mesh.vert.glsl"))?;
         let frag_shader = {
             let mut builder = ShaderBuilder::default();
-            if let TextureSlot::Texture(_) = &color_slot {
-                builder.define("HAS_COLOR_TEXTURE");
+            match shading {
+                Shading::Albedo => {
+                    if let TextureSlot::Texture(_) = color_slot {
+                        builder.define("HAS_COLOR_TEXTURE");
+                    }
+                }
+                Shading::GaussianCurvature { .. } => builder.define("FALSE_COLOR_GAUSSIAN"),
+                Shading::MeanCurvature { .. } => builder.define("FALSE_COLOR_MEAN"),
+                Shading::Zebra { .. } => builder.define("ZEBRA"),
+                Shading::Isophotes { .. } => builder.define("ISOPHOTES"),
             }
-            if normal_map.is_some() {
+            if shading.is_lit() && normal_map.is_some() {
                 builder.define("HAS_NORMAL_TEXTURE");
             }
-            if let TextureSlot::Texture(_) = &rough_metal {
+            if let (true, TextureSlot::Texture(_)) = (shading.is_lit(), &*rough_metal) {
                 builder.define("HAS_ROUGH_METAL_TEXTURE");
             }
             builder.load(shaders_dir.join("mesh.frag.glsl"))?;
@@ -134,20 +224,30 @@ impl Material {
         };
         let mut program = Program::from_shaders([vert_shader.id, frag_shader.id])?;
         program.with_binding(|progbind| {
-            match &mut color_slot {
-                TextureSlot::Texture(tex) => {
+            match (shading, &mut *color_slot) {
+                (Shading::Albedo, TextureSlot::Texture(tex)) => {
                     let unit = TextureUnit(0);
                     progbind.uniform("color").unwrap().set(unit)?;
                     tex.set_texture_unit(unit);
                 }
-                TextureSlot::Color(col) => progbind.uniform("color").unwrap().set(*col)?,
+                (Shading::Albedo, TextureSlot::Color(col)) => {
+                    progbind.uniform("color").unwrap().set(*col)?
+                }
+                (Shading::GaussianCurvature { range } | Shading::MeanCurvature { range }, _) => {
+                    progbind.uniform("curvature_range").unwrap().set(range)?
+                }
+                (Shading::Zebra { stripes } | Shading::Isophotes { stripes }, _) => {
+                    progbind.uniform("stripe_count").unwrap().set(stripes)?;
+                    // Unlit shading samples none of the material textures
+                    return Ok(());
+                }
             }
-            if let Some(tex) = &mut normal_map {
+            if let Some(tex) = normal_map {
                 let unit = TextureUnit(1);
                 progbind.uniform("normal_map").unwrap().set(unit)?;
                 tex.set_texture_unit(unit);
             }
-            match &mut rough_metal {
+            match rough_metal {
                 TextureSlot::Texture(tex) => {
                     let unit = TextureUnit(2);
                     progbind.uniform("rough_metal").unwrap().set(unit)?;
@@ -157,27 +257,7 @@ impl Material {
             }
             Ok(())
         })?;
-        Ok(Self {
-            program,
-            color_slot,
-            normal_map,
-            normal_amount: 1.,
-            rough_metal,
-        })
-    }
-
-    pub fn with_normal_amount(mut self, amt: f32) -> anyhow::Result<Material> {
-        self.normal_amount = amt;
-        self.program
-            .with_binding(|prog| {
-                if let Some(loc) = prog.uniform("normal_amount") {
-                    loc.set(self.normal_amount)
-                } else {
-                    Ok(())
-                }
-            })
-            .context("Cannot set normal amount in material")?;
-        Ok(self)
+        Ok(program)
     }
 
     pub fn draw_mesh(
@@ -195,14 +275,21 @@ impl Material {
         progbind
             .uniform("camera_pos")
             .unwrap()
-            .set(camera.transform.position)?;
-        for light_idx in 0..lights.len() {
+            .set(camera.position())?;
+        let passes = if self.shading.is_lit() {
+            lights.len()
+        } else {
+            1
+        };
+        for light_idx in 0..passes {
             framebuffer.do_clear(ClearBuffer::DEPTH).unwrap();
-            progbind
-                .uniform_block("Light", 0)
-                .unwrap()
-                .bind_block(&lights.slice(light_idx..=light_idx))
-                .unwrap();
+            if self.shading.is_lit() {
+                progbind
+                    .uniform_block("Light", 0)
+                    .unwrap()
+                    .bind_block(&lights.slice(light_idx..=light_idx))
+                    .unwrap();
+            }
             for mesh in &mut *meshes {
                 progbind
                     .uniform("model")
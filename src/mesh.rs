@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use anyhow::Context;
 use float_ord::FloatOrd;
 use glam::{vec2, vec3, Vec2, Vec3};
@@ -19,10 +21,41 @@ pub struct Vertex {
     pub position: Vec3,
     pub normal: Vec3,
     pub uv: Vec2,
+    pub tangent: Vec3,
+    /// Gaussian and mean curvature, when baked during tessellation.
+    pub curvature: Vec2,
 }
 
 impl AsVertexAttributes for Vertex {
-    type Attr = (Vec3, Vec3, Vec2);
+    type Attr = (Vec3, Vec3, Vec2, Vec3, Vec2);
+}
+
+/// CPU-side mesh data, ready to be uploaded to the GPU.
+#[derive(Debug, Clone, Default)]
+pub struct MeshData {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    pub fn upload(&self) -> anyhow::Result<Mesh> {
+        Mesh::new(self.vertices.iter().copied(), self.indices.iter().copied())
+    }
+
+    /// Index of the first vertex at the same position as each vertex, identifying the copies
+    /// made along seams and collapsed edges.
+    pub(crate) fn position_groups(&self) -> Vec<usize> {
+        let mut first = HashMap::new();
+        self.vertices
+            .iter()
+            .enumerate()
+            .map(|(i, vertex)| {
+                *first
+                    .entry(vertex.position.to_array().map(f32::to_bits))
+                    .or_insert(i)
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug)]
@@ -64,6 +97,8 @@ impl Mesh {
             position: Vec3::Y,
             uv: vec2(0.5, 1.0),
             normal: Vec3::Y,
+            tangent: Vec3::X,
+            curvature: Vec2::ZERO,
         });
         for j in 1..nlat {
             let phi = FRAC_PI_2 - j as f32 * lat_step;
@@ -74,10 +109,13 @@ impl Mesh {
                 let normal = vec3(cphi * cth, sphi, cphi * sth);
                 let position = normal * radius;
                 let uv = vec2(i as f32 / nlon as f32, 1. - j as f32 / nlat as f32);
+                let tangent = vec3(-sth, 0., cth);
                 vertices.push(Vertex {
                     position,
                     normal,
                     uv,
+                    tangent,
+                    curvature: Vec2::ZERO,
                 })
             }
         }
@@ -85,6 +123,8 @@ impl Mesh {
             position: -Vec3::Y,
             uv: vec2(0.5, 0.0),
             normal: -Vec3::Y,
+            tangent: Vec3::X,
+            curvature: Vec2::ZERO,
         });
 
         // Indices: first row connected to north pole